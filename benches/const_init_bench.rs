@@ -5,7 +5,11 @@ use criterion::{
     AxisScale, BenchmarkId, Criterion, PlotConfiguration, criterion_group, criterion_main,
 };
 use utils::*;
-mod generated_settings;
+
+// Constants generated from "settings.json" by "build.rs"
+mod generated_settings {
+    const_init::include_settings!("settings");
+}
 
 // Benchmarks where initialization of data is omitted
 fn branch_optimizations(c: &mut Criterion) {
@@ -1,14 +0,0 @@
-#![allow(dead_code)]
-// Generated file, don't modify it
-// This file is built at compile-time and contains the variable from "/home/crousti/boum/const_init/settings.json"
-
-pub const FOO: bool = true;
-pub const BAR: isize = 1;
-pub mod a {
-	pub const B: [isize; 3] = [1,2,-3];
-	pub const C: f64 = 3.14;
-	pub const D: &str = "ding!";
-}
-pub const B: [isize; 3] = [1,2,-3];
-pub const C: f64 = 3.14;
-pub const D: &str = "ding!";
@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use const_init_build::generate_constants_from_json;
+use const_init_build::Generator;
 
 fn main() {
     let manifest_path = std::env::var("CARGO_MANIFEST_DIR").unwrap();
@@ -8,15 +8,11 @@ fn main() {
     // We read the settings from "settings.json" file
     let json_input: PathBuf = [&manifest_path, "settings.json"].iter().collect();
 
-    // We output "settings.rs" containing the variables of "settings.json" as constants
-    let rust_output: PathBuf = [&manifest_path, "examples", "generated", "settings.rs"]
-        .iter()
-        .collect();
-    generate_constants_from_json(&json_input, &rust_output);
-
-    // We regenerate a file for the benches
-    let rust_output: PathBuf = [&manifest_path, "benches", "generated_settings.rs"]
-        .iter()
-        .collect();
-    generate_constants_from_json(&json_input, &rust_output);
+    // We output "settings.rs" in `OUT_DIR` containing the variables of "settings.json" as constants.
+    // The examples and the benches include it with `const_init::include_settings!("settings")`
+    Generator::new()
+        .input(&json_input)
+        .output_to_out_dir("settings")
+        .run()
+        .unwrap_or_else(|error| panic!("{error}"));
 }
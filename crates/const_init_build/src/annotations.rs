@@ -0,0 +1,90 @@
+use crate::error::{ConstInitError, ErrorKind, PathSegment};
+use json::JsonValue;
+
+// Annotations are keys describing their object or one of its keys instead of being values:
+// `"$doc": "..."` annotates the object containing it and `"$doc.port": "..."` the key `port`
+// of this object.
+const ANNOTATIONS: [&str; 2] = ["doc", "enum"];
+
+// The values of annotations and the paths of the keys they annotate,
+// the path of an annotation of the root object is empty
+pub(crate) type Annotations<T> = Vec<(Vec<PathSegment>, T)>;
+
+// Whether a key annotates its object instead of being a value
+pub(crate) fn is_annotation(key: &str) -> bool {
+    ANNOTATIONS
+        .iter()
+        .any(|name| annotated_key(key, name).is_some())
+}
+
+// `Some(None)` when `key` is the annotation `$name` of its object,
+// `Some(Some(annotated))` when it is `$name.annotated`
+fn annotated_key<'a>(key: &'a str, name: &str) -> Option<Option<&'a str>> {
+    match key.strip_prefix('$')?.strip_prefix(name)? {
+        "" => Some(None),
+        rest => rest.strip_prefix('.').map(Some),
+    }
+}
+
+// Remove the annotations `$name` from `json` and read their values with `read`
+pub(crate) fn take_annotations<T>(
+    json: &mut JsonValue,
+    name: &str,
+    read: &impl Fn(&JsonValue) -> Result<T, ConstInitError>,
+) -> Result<Annotations<T>, ConstInitError> {
+    let mut annotations = Vec::new();
+    take(json, name, read, &mut Vec::new(), &mut annotations)?;
+    Ok(annotations)
+}
+
+fn take<T>(
+    json: &mut JsonValue,
+    name: &str,
+    read: &impl Fn(&JsonValue) -> Result<T, ConstInitError>,
+    key_path: &mut Vec<PathSegment>,
+    annotations: &mut Annotations<T>,
+) -> Result<(), ConstInitError> {
+    match json {
+        JsonValue::Object(object) => {
+            let keys: Vec<String> = object.iter().map(|(key, _)| key.to_string()).collect();
+            for key in &keys {
+                let Some(annotated) = annotated_key(key, name) else {
+                    continue;
+                };
+                let value = object.remove(key).unwrap_or(JsonValue::Null);
+                let value = read(&value).map_err(|error| error.in_key(key))?;
+                let path = match annotated {
+                    Some(annotated) if object.get(annotated).is_none() => {
+                        return Err(ConstInitError::new(ErrorKind::Unsupported(format!(
+                            "`{key}` annotates the key `{annotated}` which is not in its object"
+                        )))
+                        .in_key(key));
+                    }
+                    Some(annotated) => {
+                        [key_path, &[PathSegment::Key(annotated.to_string())][..]].concat()
+                    }
+                    None => key_path.clone(),
+                };
+                annotations.push((path, value));
+            }
+            for key in keys.iter().filter(|key| !is_annotation(key)) {
+                if let Some(value) = object.get_mut(key) {
+                    key_path.push(PathSegment::Key(key.to_string()));
+                    take(value, name, read, key_path, annotations)
+                        .map_err(|error| error.in_key(key))?;
+                    key_path.pop();
+                }
+            }
+        }
+        JsonValue::Array(values) => {
+            for (index, value) in values.iter_mut().enumerate() {
+                key_path.push(PathSegment::Index(index));
+                take(value, name, read, key_path, annotations)
+                    .map_err(|error| error.in_index(index))?;
+                key_path.pop();
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
@@ -0,0 +1,227 @@
+use crate::enums::Enums;
+use crate::error::{ConstInitError, ErrorKind, PathSegment};
+use crate::generator::string_literal;
+use crate::naming::{Namespace, Naming, Scope, identifier};
+use json::JsonValue;
+
+// The `cargo:rustc-cfg` instructions setting a cfg for each true boolean of `json` and for
+// the string values at `value_keys` (e.g. `server.mode`), along with the `rustc-check-cfg`
+// instructions declaring them: every boolean is declared so that `#[cfg(not(...))]` can be
+// used when it is false, and the strings with the values of their `$enum` if any. The names
+// of the cfgs are the prefix followed by the names of the modules and of the constant in
+// snake case, separated by `_`, e.g. `const_init_server_tls`. Two keys giving the same name,
+// e.g. `a.b_c` and `a_b.c`, are an error.
+pub(crate) fn cfg_instructions(
+    json: &JsonValue,
+    prefix: &str,
+    value_keys: &[String],
+    enums: &Enums,
+    naming: Naming,
+) -> Result<Vec<String>, ConstInitError> {
+    let mut instructions = Vec::new();
+    let mut names = Scope::default();
+    boolean_cfgs(
+        json,
+        prefix,
+        &mut Vec::new(),
+        naming,
+        &mut names,
+        &mut instructions,
+    )?;
+    for (index, keys) in value_keys.iter().enumerate() {
+        // A key given twice sets its cfg once
+        if value_keys[..index].contains(keys) {
+            continue;
+        }
+        let key_path: Vec<PathSegment> = keys
+            .split('.')
+            .map(|key| PathSegment::Key(key.to_string()))
+            .collect();
+        let value = key_path
+            .iter()
+            .try_fold(json, |json, segment| match segment {
+                PathSegment::Key(key) if json.has_key(key) => Some(&json[key.as_str()]),
+                _ => None,
+            })
+            .ok_or_else(|| {
+                ConstInitError::new(ErrorKind::InvalidGenerator(format!(
+                    "the cfg value `{keys}` is not a key of the configuration"
+                )))
+            })?;
+        let name = keys.split('.').fold(prefix.to_string(), |name, key| {
+            format!("{name}_{}", cfg_word(key, naming))
+        });
+        let literal = match value.as_str() {
+            Some(value) if !value.chars().any(char::is_control) => string_literal(value),
+            _ => {
+                let error = ConstInitError::new(ErrorKind::Unsupported(
+                    "a cfg value must be a string on a single line".to_string(),
+                ));
+                return Err(error.in_key_path(&key_path));
+            }
+        };
+        names
+            .declare(Namespace::Value, &name, &key_path)
+            .map_err(|error| error.in_key_path(&key_path))?;
+        let values = match enums.get(&key_path) {
+            Some(allowed) => {
+                let allowed: Vec<String> =
+                    allowed.iter().map(|value| string_literal(value)).collect();
+                allowed.join(", ")
+            }
+            None => "any()".to_string(),
+        };
+        instructions.push(format!(
+            "cargo:rustc-check-cfg=cfg({name}, values({values}))"
+        ));
+        instructions.push(format!("cargo:rustc-cfg={name}={literal}"));
+    }
+    Ok(instructions)
+}
+
+fn boolean_cfgs(
+    json: &JsonValue,
+    name: &str,
+    key_path: &mut Vec<PathSegment>,
+    naming: Naming,
+    names: &mut Scope,
+    instructions: &mut Vec<String>,
+) -> Result<(), ConstInitError> {
+    match json {
+        JsonValue::Object(object) => {
+            for (key, value) in object.iter() {
+                let name = format!("{name}_{}", cfg_word(key, naming));
+                key_path.push(PathSegment::Key(key.to_string()));
+                boolean_cfgs(value, &name, key_path, naming, names, instructions)?;
+                key_path.pop();
+            }
+        }
+        JsonValue::Boolean(value) => {
+            names
+                .declare(Namespace::Value, name, key_path)
+                .map_err(|error| error.in_key_path(key_path))?;
+            instructions.push(format!("cargo:rustc-check-cfg=cfg({name})"));
+            if *value {
+                instructions.push(format!("cargo:rustc-cfg={name}"));
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+// Whether `prefix` can start the name of a cfg: an identifier which is not a keyword
+pub(crate) fn is_cfg_prefix(prefix: &str) -> bool {
+    identifier(prefix) == prefix
+}
+
+// The part of the name of a cfg coming from a key, keywords don't need to be escaped
+// since the name starts with the prefix
+fn cfg_word(key: &str, naming: Naming) -> String {
+    let word = identifier(&naming.module(key));
+    match word.strip_prefix("r#") {
+        Some(keyword) => keyword.to_string(),
+        None => word,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cfg_instructions() {
+        let json = json::parse(
+            r#"{
+                "fastMode": true,
+                "telemetry": false,
+                "server": { "tls": true, "mode": "fast", "port": 80 },
+                "flags": [true]
+            }"#,
+        )
+        .unwrap();
+        let instructions = cfg_instructions(
+            &json,
+            "const_init",
+            &["server.mode".to_string()],
+            &Enums::default(),
+            Naming::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            instructions,
+            [
+                "cargo:rustc-check-cfg=cfg(const_init_fast_mode)",
+                "cargo:rustc-cfg=const_init_fast_mode",
+                "cargo:rustc-check-cfg=cfg(const_init_telemetry)",
+                "cargo:rustc-check-cfg=cfg(const_init_server_tls)",
+                "cargo:rustc-cfg=const_init_server_tls",
+                "cargo:rustc-check-cfg=cfg(const_init_server_mode, values(any()))",
+                "cargo:rustc-cfg=const_init_server_mode=\"fast\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_cfg_values_are_an_error() {
+        let json = json::parse(r#"{ "server": { "port": 80, "name": "a\nb" } }"#).unwrap();
+        let error = |key: &str| {
+            cfg_instructions(
+                &json,
+                "const_init",
+                &[key.to_string()],
+                &Enums::default(),
+                Naming::default(),
+            )
+            .unwrap_err()
+        };
+        assert!(matches!(
+            error("server.host").kind(),
+            ErrorKind::InvalidGenerator(_)
+        ));
+        let port = error("server.port");
+        assert!(matches!(port.kind(), ErrorKind::Unsupported(_)));
+        assert_eq!(port.key_path().as_deref(), Some("server.port"));
+        assert!(matches!(
+            error("server.name").kind(),
+            ErrorKind::Unsupported(_)
+        ));
+    }
+
+    #[test]
+    fn duplicate_cfg_names_are_an_error() {
+        let json = json::parse(r#"{ "a": { "b_c": true }, "a_b": { "c": "x" } }"#).unwrap();
+        let instructions = |value_keys: &[&str]| {
+            let value_keys: Vec<String> = value_keys.iter().map(|key| key.to_string()).collect();
+            cfg_instructions(
+                &json,
+                "const_init",
+                &value_keys,
+                &Enums::default(),
+                Naming::default(),
+            )
+        };
+        assert_eq!(instructions(&[]).unwrap().len(), 2);
+        let error = instructions(&["a_b.c"]).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::DuplicateIdentifier { identifier, first_key }
+                if identifier == "const_init_a_b_c" && first_key == "a.b_c"
+        ));
+        assert_eq!(error.key_path().as_deref(), Some("a_b.c"));
+
+        let json = json::parse(r#"{ "fastMode": true, "fast-mode": false }"#).unwrap();
+        let error =
+            cfg_instructions(&json, "cfg", &[], &Enums::default(), Naming::default()).unwrap_err();
+        assert_eq!(error.key_path().as_deref(), Some("fast-mode"));
+    }
+
+    #[test]
+    fn test_is_cfg_prefix() {
+        assert!(is_cfg_prefix("const_init"));
+        assert!(is_cfg_prefix("_settings2"));
+        for invalid in ["", "_", "2fa", "const-init", "my prefix", "fn", "self"] {
+            assert!(!is_cfg_prefix(invalid), "{invalid}");
+        }
+    }
+}
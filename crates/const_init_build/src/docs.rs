@@ -0,0 +1,284 @@
+use crate::annotations::{Annotations, take_annotations};
+use crate::error::{ConstInitError, ErrorKind, PathSegment};
+use crate::generator::{KeyLocations, Layer, display_path};
+use crate::merge::contains;
+use json::JsonValue;
+use std::collections::HashMap;
+
+// Documentation of the generated modules and constants, written as `///` comments above
+// them so that `cargo doc` describes the settings. The documentation of a key is its
+// `$doc` annotation, or else the comment right above it in the configuration file,
+// followed by the file and the line defining it. Its code blocks are marked as `text`
+// so that they aren't run as doctests of the crate including the generated file.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Docs {
+    docs: HashMap<Vec<PathSegment>, String>,
+}
+
+// A configuration file with its lines and the locations of its keys, found once
+// for all the keys to document
+struct LayerKeys<'a> {
+    layer: &'a Layer<'a>,
+    lines: Vec<&'a str>,
+    locations: KeyLocations,
+}
+
+impl Docs {
+    // Documentation of the keys of `json`, whose annotations were taken out by
+    // `take_doc_annotations`, and which was merged from `layers`
+    pub(crate) fn collect(
+        json: &JsonValue,
+        annotations: &Annotations<String>,
+        layers: &[Layer],
+    ) -> Docs {
+        // The first annotation of a key is kept, like the first of duplicate keys
+        let annotations: HashMap<&[PathSegment], &String> = annotations
+            .iter()
+            .rev()
+            .map(|(key_path, doc)| (key_path.as_slice(), doc))
+            .collect();
+        let layers: Vec<LayerKeys> = layers
+            .iter()
+            .map(|layer| LayerKeys {
+                layer,
+                lines: layer.contents.lines().collect(),
+                locations: layer.format.key_locations(&layer.contents),
+            })
+            .collect();
+        let mut docs = Docs::default();
+        if let Some(doc) = annotations.get([].as_slice()) {
+            docs.docs.insert(Vec::new(), escape_code_blocks(doc));
+        }
+        docs.insert(json, &mut Vec::new(), &annotations, &layers);
+        docs
+    }
+
+    fn insert(
+        &mut self,
+        json: &JsonValue,
+        key_path: &mut Vec<PathSegment>,
+        annotations: &HashMap<&[PathSegment], &String>,
+        layers: &[LayerKeys],
+    ) {
+        let JsonValue::Object(object) = json else {
+            return;
+        };
+        for (key, value) in object.iter() {
+            key_path.push(PathSegment::Key(key.to_owned()));
+            let defining_layers = layers
+                .iter()
+                .rev()
+                .filter(|keys| contains(&keys.layer.json, key_path));
+            let description = annotations
+                .get(key_path.as_slice())
+                .map(|doc| doc.to_string())
+                .or_else(|| {
+                    defining_layers
+                        .clone()
+                        .find_map(|keys| layer_comment(keys, key_path))
+                });
+            // The value comes from the last file defining it
+            let source = defining_layers.clone().next().and_then(|keys| {
+                let location = keys.locations.get(key_path.as_slice())?;
+                Some(format!(
+                    "Defined in `{}` at line {}.",
+                    display_path(keys.layer.file),
+                    location.line
+                ))
+            });
+            let doc: Vec<String> = description.into_iter().chain(source).collect();
+            if !doc.is_empty() {
+                self.docs
+                    .insert(key_path.clone(), escape_code_blocks(&doc.join("\n\n")));
+            }
+            self.insert(value, key_path, annotations, layers);
+            key_path.pop();
+        }
+    }
+
+    // The documentation of the item generated for `key_path`, the empty path for the file
+    pub(crate) fn get(&self, key_path: &[PathSegment]) -> Option<&str> {
+        self.docs.get(key_path).map(String::as_str)
+    }
+}
+
+// Give the `text` info string to the code blocks of `doc`: rustdoc tests the ones without
+// info string or with a Rust one. The other lines are kept as they are, they are written
+// after `/// ` so that `*/` or a leading `!` are only text.
+fn escape_code_blocks(doc: &str) -> String {
+    // The opening fence of the current code block
+    let mut opening: Option<&str> = None;
+    let mut lines = Vec::new();
+    for line in doc.lines() {
+        match (fence(line), opening) {
+            // Closed by a fence of the same character, at least as long and without info string
+            (Some((marker, info)), Some(open))
+                if marker.starts_with(&open[..1])
+                    && marker.len() >= open.len()
+                    && info.trim().is_empty() =>
+            {
+                opening = None;
+                lines.push(line.to_string());
+            }
+            (Some((marker, _)), None) => {
+                opening = Some(marker);
+                let indent = &line[..line.len() - line.trim_start().len()];
+                lines.push(format!("{indent}{marker}text"));
+            }
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines.join("\n")
+}
+
+// The fence (at least 3 backticks or tildes) starting `line` and the info string after it
+fn fence(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim_start();
+    let char = line
+        .chars()
+        .next()
+        .filter(|char| matches!(char, '`' | '~'))?;
+    let length = line.len() - line.trim_start_matches(char).len();
+    (length >= 3).then(|| line.split_at(length))
+}
+
+// Remove the `$doc` annotations from `json`: `"$doc": "..."` documents the object
+// containing it, and `"$doc.port": "..."` the key `port` of this object
+pub(crate) fn take_doc_annotations(
+    json: &mut JsonValue,
+) -> Result<Annotations<String>, ConstInitError> {
+    take_annotations(json, "doc", &|value| match value.as_str() {
+        Some(doc) => Ok(doc.to_string()),
+        None => Err(ConstInitError::new(ErrorKind::Unsupported(
+            "a `$doc` annotation must be a string".to_string(),
+        ))),
+    })
+}
+
+// The comment right above the key at `key_path` in the file of `keys`
+fn layer_comment(keys: &LayerKeys, key_path: &[PathSegment]) -> Option<String> {
+    let line_comment = keys.layer.format.line_comment()?;
+    let location = keys.locations.get(key_path)?;
+    comment_above(&keys.lines, line_comment, location.line)
+}
+
+// The comment lines right above `line`, without their comment markers. JSON5 block
+// comments (`/* ... */`) are read as well, a leading `*` on their lines is removed.
+fn comment_above(lines: &[&str], line_comment: &str, line: usize) -> Option<String> {
+    let before = line.saturating_sub(1).min(lines.len());
+    let mut lines = lines[..before].iter().copied();
+    let mut comment = Vec::new();
+    while let Some(previous) = lines.next_back().map(str::trim) {
+        if let Some(text) = previous.strip_prefix(line_comment) {
+            // `///` and `##` are read as `//` and `#`
+            let text = text.trim_start_matches(&line_comment[..1]);
+            comment.push(text.strip_prefix(' ').unwrap_or(text));
+        } else if line_comment == "//" && previous.ends_with("*/") {
+            let mut block = previous.strip_suffix("*/");
+            while let Some(text) = block {
+                if let Some(text) = text.strip_prefix("/*") {
+                    comment.push(text.trim_start_matches('*').trim());
+                    break;
+                }
+                // A block comment starting after a value is not the comment of the key
+                if text.contains("/*") {
+                    return None;
+                }
+                let text = text.trim();
+                comment.push(text.strip_prefix('*').unwrap_or(text).trim());
+                block = lines.next_back().map(str::trim);
+            }
+            // An unterminated block comment
+            block?;
+        } else {
+            break;
+        }
+    }
+    // The lines were read from the bottom
+    comment.reverse();
+    let text = comment.join("\n").trim_matches('\n').to_string();
+    (!text.trim().is_empty()).then_some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comment_above() {
+        let lines: Vec<&str> =
+            "a = 1\n\n# Port of the server,\n#\n## between 1 and 65535\nport = 80"
+                .lines()
+                .collect();
+        assert_eq!(
+            comment_above(&lines, "#", 6).as_deref(),
+            Some("Port of the server,\n\nbetween 1 and 65535")
+        );
+        // A blank line separates a comment from the key
+        assert_eq!(comment_above(&lines, "#", 2), None);
+        assert_eq!(comment_above(&lines, "#", 1), None);
+
+        let lines: Vec<&str> = "{\n  /**\n   * Size of the buffer\n   */\n  size: 4,\n  /* Name */ name: 'a',\n  b: 1, /* not b */\n  c: 2\n}"
+            .lines()
+            .collect();
+        assert_eq!(
+            comment_above(&lines, "//", 5).as_deref(),
+            Some("Size of the buffer")
+        );
+        assert_eq!(comment_above(&lines, "//", 8), None);
+    }
+
+    #[test]
+    fn test_escape_code_blocks() {
+        let doc =
+            "Address, e.g.\n```\nlet a = 1;\n```\n\n  ~~~~rust\n~~~\n~~~~~\n```json\n{}\n`````";
+        assert_eq!(
+            escape_code_blocks(doc),
+            "Address, e.g.\n```text\nlet a = 1;\n```\n\n  ~~~~text\n~~~\n~~~~~\n```text\n{}\n`````"
+        );
+        // Only the fences are changed
+        let doc = "!important */ `code` ``\n/// ``";
+        assert_eq!(escape_code_blocks(doc), doc);
+    }
+
+    #[test]
+    fn test_take_doc_annotations() {
+        let mut json = json::parse(
+            r#"{
+                "$doc": "Settings of the application",
+                "server": { "$doc": "The server", "$doc.port": "Its port", "port": 80 },
+                "backends": [{ "$doc": "Ignored", "name": "a" }]
+            }"#,
+        )
+        .unwrap();
+        let annotations = take_doc_annotations(&mut json).unwrap();
+        assert_eq!(
+            json.dump(),
+            r#"{"server":{"port":80},"backends":[{"name":"a"}]}"#
+        );
+        let key = |key: &str| PathSegment::Key(key.to_string());
+        assert_eq!(
+            annotations,
+            vec![
+                (vec![], "Settings of the application".to_string()),
+                (vec![key("server")], "The server".to_string()),
+                (vec![key("server"), key("port")], "Its port".to_string()),
+                (
+                    vec![key("backends"), PathSegment::Index(0)],
+                    "Ignored".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_doc_annotations_are_an_error() {
+        let mut json = json::parse(r#"{ "a": { "$doc.b": "missing", "c": 1 } }"#).unwrap();
+        let error = take_doc_annotations(&mut json).unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::Unsupported(_)));
+        assert_eq!(error.key_path().as_deref(), Some("a.$doc.b"));
+
+        let mut json = json::parse(r#"{ "$doc": 1 }"#).unwrap();
+        assert!(take_doc_annotations(&mut json).is_err());
+    }
+}
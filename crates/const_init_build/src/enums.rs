@@ -0,0 +1,219 @@
+use crate::annotations::{Annotations, take_annotations};
+use crate::error::{ConstInitError, ErrorKind, PathSegment};
+use crate::generator::string_literal;
+use crate::naming::{Naming, identifier};
+use json::JsonValue;
+
+// The allowed values of string keys, given by `"$enum.mode": ["fast", "safe"]` annotations.
+// These strings become enums with one variant per value instead of `&str`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Enums {
+    enums: Annotations<Vec<String>>,
+}
+
+// An enum generated for a string key
+pub(crate) struct EnumType<'a> {
+    pub(crate) name: String,
+    values: &'a [String],
+    variants: Vec<String>,
+}
+
+impl Enums {
+    // Remove the `$enum` annotations from `json`, the keys they annotate must be strings
+    pub(crate) fn take_annotations(json: &mut JsonValue) -> Result<Enums, ConstInitError> {
+        let enums = take_annotations(json, "enum", &|value| {
+            let values: Option<Vec<String>> = value
+                .members()
+                .map(|value| value.as_str().map(str::to_string))
+                .collect();
+            match values {
+                Some(values) if !values.is_empty() => Ok(values),
+                _ => Err(invalid_enum(
+                    "a `$enum` annotation must be a non-empty array of strings",
+                )),
+            }
+        })?;
+        for (key_path, _) in &enums {
+            let value = key_path
+                .iter()
+                .try_fold(&*json, |json, segment| match segment {
+                    PathSegment::Key(key) => json.has_key(key).then(|| &json[key.as_str()]),
+                    PathSegment::Index(index) => json.is_array().then(|| &json[*index]),
+                });
+            if !value.is_some_and(JsonValue::is_string) {
+                let error = invalid_enum("a `$enum` annotation must annotate a string");
+                return Err(error.in_key_path(key_path));
+            }
+        }
+        Ok(Enums { enums })
+    }
+
+    // Add the allowed values of other keys, the ones already known take precedence
+    pub(crate) fn extend(&mut self, enums: Annotations<Vec<String>>) {
+        self.enums.extend(enums);
+    }
+
+    // The allowed values of the string at `key_path`. Indexes are skipped so that
+    // an annotation in an element of an array of objects applies to every element.
+    pub(crate) fn get(&self, key_path: &[PathSegment]) -> Option<&[String]> {
+        let keys = |key_path: &[PathSegment]| {
+            key_path
+                .iter()
+                .filter(|segment| matches!(segment, PathSegment::Key(_)))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        self.enums
+            .iter()
+            .find(|(path, _)| keys(path) == keys(key_path))
+            .map(|(_, values)| values.as_slice())
+    }
+}
+
+impl<'a> EnumType<'a> {
+    // The enum `name` whose variants are the values in `naming` case, e.g. `Fast` for `fast`
+    pub(crate) fn new(
+        name: String,
+        values: &'a [String],
+        naming: Naming,
+    ) -> Result<Self, ConstInitError> {
+        let mut variants: Vec<String> = Vec::new();
+        for value in values {
+            let variant = identifier(&naming.type_name(value));
+            if let Some(first) = variants.iter().position(|first| *first == variant) {
+                return Err(invalid_enum(&format!(
+                    "the values {:?} and {value:?} both become the variant `{variant}`",
+                    values[first]
+                )));
+            }
+            variants.push(variant);
+        }
+        Ok(EnumType {
+            name,
+            values,
+            variants,
+        })
+    }
+
+    // The definition of the enum, with a `const fn as_str` giving back the values
+    pub(crate) fn definition(&self, visibility: &str) -> String {
+        let name = &self.name;
+        let mut definition = "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\n".to_string();
+        definition.push_str(&format!("{visibility} enum {name} {{\n"));
+        for variant in &self.variants {
+            definition.push_str(&format!("\t{variant},\n"));
+        }
+        definition.push_str("}\n");
+        definition.push_str(&format!("impl {name} {{\n"));
+        definition.push_str("\t/// The value as written in the configuration file\n");
+        definition.push_str(&format!(
+            "\t{visibility} const fn as_str(self) -> &'static str {{\n"
+        ));
+        definition.push_str("\t\tmatch self {\n");
+        for (variant, value) in self.variants.iter().zip(self.values) {
+            definition.push_str(&format!(
+                "\t\t\t{name}::{variant} => {},\n",
+                string_literal(value)
+            ));
+        }
+        definition.push_str("\t\t}\n\t}\n}\n");
+        definition
+    }
+
+    // The variant of a value, which must be one of the allowed values
+    pub(crate) fn literal(&self, json: &JsonValue) -> Result<String, ConstInitError> {
+        let value = json.as_str().ok_or_else(|| {
+            ConstInitError::new(ErrorKind::TypeMismatch {
+                value: json.dump(),
+                rust_type: self.name.clone(),
+            })
+        })?;
+        match self.values.iter().position(|allowed| allowed == value) {
+            Some(index) => Ok(format!("{}::{}", self.name, self.variants[index])),
+            None => Err(ConstInitError::new(ErrorKind::UnexpectedValue {
+                value: value.to_string(),
+                allowed: self.values.to_vec(),
+            })),
+        }
+    }
+}
+
+fn invalid_enum(message: &str) -> ConstInitError {
+    ConstInitError::new(ErrorKind::Unsupported(message.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enum_type() {
+        let values = ["fast".to_string(), "safe-mode".to_string()];
+        let mode = EnumType::new("Mode".to_string(), &values, Naming::default()).unwrap();
+        assert_eq!(
+            mode.definition("pub"),
+            "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mode {
+\tFast,
+\tSafeMode,
+}
+impl Mode {
+\t/// The value as written in the configuration file
+\tpub const fn as_str(self) -> &'static str {
+\t\tmatch self {
+\t\t\tMode::Fast => \"fast\",
+\t\t\tMode::SafeMode => \"safe-mode\",
+\t\t}
+\t}
+}
+"
+        );
+        assert_eq!(mode.literal(&"safe-mode".into()).unwrap(), "Mode::SafeMode");
+
+        let error = mode.literal(&"slow".into()).unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::UnexpectedValue { .. }));
+        assert_eq!(
+            error.kind().to_string(),
+            r#""slow" is not one of the allowed values: "fast", "safe-mode""#
+        );
+        let error = mode.literal(&1.into()).unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::TypeMismatch { .. }));
+
+        let values = ["fast".to_string(), "Fast".to_string()];
+        assert!(EnumType::new("Mode".to_string(), &values, Naming::default()).is_err());
+    }
+
+    #[test]
+    fn test_enum_annotations() {
+        let mut json = json::parse(
+            r#"{
+                "server": { "$enum.mode": ["fast", "safe"], "mode": "fast" },
+                "backends": [{ "$enum.kind": ["a", "b"], "kind": "a" }, { "kind": "b" }]
+            }"#,
+        )
+        .unwrap();
+        let enums = Enums::take_annotations(&mut json).unwrap();
+        assert_eq!(
+            json.dump(),
+            r#"{"server":{"mode":"fast"},"backends":[{"kind":"a"},{"kind":"b"}]}"#
+        );
+        let key = |key: &str| PathSegment::Key(key.to_string());
+        assert!(enums.get(&[key("server"), key("mode")]).is_some());
+        let kind = [key("backends"), PathSegment::Index(1), key("kind")];
+        assert_eq!(enums.get(&kind).map(<[String]>::len), Some(2));
+
+        for invalid in [
+            r#"{ "$enum.a": [], "a": "x" }"#,
+            r#"{ "$enum.a": ["x", 1], "a": "x" }"#,
+            r#"{ "$enum.a": ["x"], "a": 1 }"#,
+            r#"{ "$enum": ["x"] }"#,
+        ] {
+            let mut json = json::parse(invalid).unwrap();
+            let error = Enums::take_annotations(&mut json).unwrap_err();
+            assert!(
+                matches!(error.kind(), ErrorKind::Unsupported(_)),
+                "{invalid}"
+            );
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use crate::error::{ConstInitError, ErrorKind};
+use crate::integer::Integer;
+use crate::json::{as_float, parse_json};
+use crate::naming::{Naming, identifier};
+use json::JsonValue;
+
+// Replace the values of `json` with the environment variables named after them: the prefix
+// followed by the names of the modules and of the constant in uppercase, separated by `__`,
+// e.g. `CONST_INIT__SERVER__PORT`. Elements of arrays of objects are named after their index.
+// The variables are read with `env` and `names` are the names of all the variables, both are
+// given by the build script. Returns the names of the variables consulted.
+pub(crate) fn apply_env_overrides(
+    json: &mut JsonValue,
+    prefix: &str,
+    naming: Naming,
+    env: &impl Fn(&str) -> Option<String>,
+    names: impl IntoIterator<Item = String>,
+) -> Result<Vec<String>, ConstInitError> {
+    let mut variables = Vec::new();
+    override_values(json, prefix, naming, env, &mut variables)?;
+
+    // A variable with the prefix but no value to override is most likely a typo
+    let prefix = format!("{prefix}__");
+    let unknown = names
+        .into_iter()
+        .find(|variable| variable.starts_with(&prefix) && !variables.contains(variable));
+    if let Some(variable) = unknown {
+        return Err(invalid_override(&variable, "it doesn't match any key"));
+    }
+    Ok(variables)
+}
+
+fn override_values(
+    json: &mut JsonValue,
+    variable: &str,
+    naming: Naming,
+    env: &impl Fn(&str) -> Option<String>,
+    variables: &mut Vec<String>,
+) -> Result<(), ConstInitError> {
+    match json {
+        JsonValue::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                let variable = format!("{variable}__{}", identifier(&naming.constant(key)));
+                override_values(value, &variable, naming, env, variables)
+                    .map_err(|error| error.in_key(key))?;
+            }
+        }
+        JsonValue::Array(values) if values.iter().any(JsonValue::is_object) => {
+            for (index, value) in values.iter_mut().enumerate() {
+                let variable = format!("{variable}__{index}");
+                override_values(value, &variable, naming, env, variables)
+                    .map_err(|error| error.in_index(index))?;
+            }
+        }
+        _ => {
+            if let Some(value) = env(variable) {
+                *json = parse_override(json, variable, &value)?;
+            }
+            variables.push(variable.to_string());
+        }
+    }
+    Ok(())
+}
+
+// Parse the value of a variable according to the type of the value it overrides
+fn parse_override(
+    current: &JsonValue,
+    variable: &str,
+    value: &str,
+) -> Result<JsonValue, ConstInitError> {
+    let parsed = || parse_json(value).map_err(|_| invalid_override(variable, "invalid JSON"));
+    let json = match current {
+        JsonValue::Short(_) | JsonValue::String(_) => JsonValue::String(value.to_string()),
+        JsonValue::Boolean(_) => match value {
+            "true" => JsonValue::Boolean(true),
+            "false" => JsonValue::Boolean(false),
+            _ => return Err(invalid_override(variable, "expected `true` or `false`")),
+        },
+        JsonValue::Number(number) => {
+            let JsonValue::Number(value) = parsed()? else {
+                return Err(invalid_override(variable, "expected a number"));
+            };
+            match (Integer::from_number(number), Integer::from_number(&value)) {
+                (Some(_), None) => {
+                    return Err(invalid_override(variable, "expected an integer"));
+                }
+                // Integers replacing floats are still floats
+                (None, Some(_)) => JsonValue::Number(as_float(value)),
+                _ => JsonValue::Number(value),
+            }
+        }
+        JsonValue::Array(_) => match parsed()? {
+            JsonValue::Array(values) => JsonValue::Array(values),
+            _ => return Err(invalid_override(variable, "expected a JSON array")),
+        },
+        // The type of a null value is unknown, its override is JSON or a string
+        _ => parsed().unwrap_or_else(|_| JsonValue::String(value.to_string())),
+    };
+    Ok(json)
+}
+
+fn invalid_override(variable: &str, message: &str) -> ConstInitError {
+    ConstInitError::new(ErrorKind::InvalidOverride {
+        variable: variable.to_string(),
+        message: message.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_override() {
+        let parse = |current: &str, value: &str| {
+            parse_override(&json::parse(current).unwrap(), "VARIABLE", value)
+                .map(|json| json.dump())
+        };
+        assert_eq!(parse(r#""a""#, "9000").unwrap(), r#""9000""#);
+        assert_eq!(parse("true", "false").unwrap(), "false");
+        assert_eq!(parse("8080", "9000").unwrap(), "9000");
+        assert_eq!(parse("0.5", "2").unwrap(), "2.0");
+        assert_eq!(parse("[1, 2]", "[3]").unwrap(), "[3]");
+        assert_eq!(parse("null", "3").unwrap(), "3");
+        assert_eq!(parse("null", "localhost").unwrap(), r#""localhost""#);
+
+        assert!(parse("true", "yes").is_err());
+        assert!(parse("8080", "90.5").is_err());
+        assert!(parse("8080", "port").is_err());
+        let error = parse("[1, 2]", "3").unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::InvalidOverride { variable, .. } if variable == "VARIABLE"
+        ));
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let mut parsed = json::parse(
+            r#"{ "server": { "port": 8080, "maxConnections": 10 }, "backends": [{ "weight": 1 }] }"#,
+        )
+        .unwrap();
+        let apply = |json: &mut JsonValue, prefix: &str, variables: &[(&str, &str)]| {
+            let env = |name: &str| {
+                variables
+                    .iter()
+                    .find(|(variable, _)| *variable == name)
+                    .map(|(_, value)| value.to_string())
+            };
+            let names = variables.iter().map(|(variable, _)| variable.to_string());
+            apply_env_overrides(json, prefix, Naming::SnakeCase, &env, names)
+        };
+        let variables = [
+            ("ENV_TEST__SERVER__PORT", "9000"),
+            ("ENV_TEST__BACKENDS__0__WEIGHT", "3"),
+            ("OTHER__SERVER__PORT", "1"),
+        ];
+        let variables = apply(&mut parsed, "ENV_TEST", &variables).unwrap();
+        assert_eq!(
+            variables,
+            [
+                "ENV_TEST__SERVER__PORT",
+                "ENV_TEST__SERVER__MAX_CONNECTIONS",
+                "ENV_TEST__BACKENDS__0__WEIGHT"
+            ]
+        );
+        assert_eq!(
+            parsed.dump(),
+            r#"{"server":{"port":9000,"maxConnections":10},"backends":[{"weight":3}]}"#
+        );
+
+        let variables = [("ENV_TEST__SERVER__HOST", "localhost")];
+        let error = apply(&mut parsed, "ENV_TEST", &variables).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::InvalidOverride { variable, .. } if variable == "ENV_TEST__SERVER__HOST"
+        ));
+
+        let variables = [("ENV_TEST__SERVER__PORT", "port")];
+        let error = apply(&mut parsed, "ENV_TEST", &variables).unwrap_err();
+        assert_eq!(error.key_path().as_deref(), Some("server.port"));
+    }
+}
@@ -0,0 +1,308 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Error returned by the `try_generate_*` functions when the constants
+/// can't be generated from a configuration file.
+///
+/// Besides the [`ErrorKind`], it tells which file, which key of this file
+/// (e.g. `a.h.j.k`) and, when it can be found, which line and column are at fault.
+#[derive(Debug)]
+pub struct ConstInitError {
+    kind: Box<ErrorKind>,
+    key_path: Vec<PathSegment>,
+    file: Option<PathBuf>,
+    location: Option<Location>,
+}
+
+/// The different reasons for which generating constants can fail
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The input file can't be read
+    ReadInput(std::io::Error),
+    /// The output rust file can't be written
+    WriteOutput(std::io::Error),
+    /// The input file is not valid for its format
+    Parse(String),
+    /// A `null` value was found whose type can't be worked out, neither from the type map
+    /// nor from the other values of its array
+    NullValue,
+    /// A `NaN` value was found
+    NanValue,
+    /// An infinite value was found
+    InfiniteValue,
+    /// An array contains values of different types
+    MixedArray,
+    /// An integer doesn't fit in the Rust type chosen for it
+    IntegerOutOfRange { value: String, rust_type: String },
+    /// A value doesn't match the Rust type given to it by the type map
+    TypeMismatch { value: String, rust_type: String },
+    /// A number can't be represented exactly by the float type chosen for it,
+    /// `closest` is the value it would become
+    InexactNumber {
+        value: String,
+        rust_type: String,
+        closest: String,
+    },
+    /// A string is not one of the values allowed for its key, e.g. by a `$enum` annotation
+    UnexpectedValue { value: String, allowed: Vec<String> },
+    /// Two keys are turned into the same Rust identifier, e.g. `max-connections` and
+    /// `max_connections`. The error is at the second key, `first_key` is the path of the first one.
+    DuplicateIdentifier {
+        identifier: String,
+        first_key: String,
+    },
+    /// An environment variable overriding a value can't be parsed as the type of this value
+    InvalidOverride { variable: String, message: String },
+    /// The type map is not valid
+    InvalidTypeMap(String),
+    /// The JSON Schema given to the [`Generator`](crate::Generator) is not valid
+    InvalidSchema(String),
+    /// A value doesn't match the JSON Schema of the configuration
+    SchemaViolation(String),
+    /// A value which has no Rust equivalent was found
+    Unsupported(String),
+    /// The [`Generator`](crate::Generator) is missing an option or has incompatible options
+    InvalidGenerator(String),
+}
+
+/// A position in a configuration file, both line and column start at 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+// A part of the path leading to a value in a configuration file
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+impl ConstInitError {
+    pub(crate) fn new(kind: ErrorKind) -> Self {
+        ConstInitError {
+            kind: Box::new(kind),
+            key_path: Vec::new(),
+            file: None,
+            location: None,
+        }
+    }
+
+    /// The reason of the error
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// Path of the faulty key in the configuration file, e.g. `a.h.j.k` or `a.g[1]`
+    pub fn key_path(&self) -> Option<String> {
+        match self.key_path.is_empty() {
+            true => None,
+            false => Some(format_key_path(&self.key_path)),
+        }
+    }
+
+    /// The configuration file in which the error happened
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// Line and column of the error in the configuration file
+    pub fn location(&self) -> Option<Location> {
+        self.location
+    }
+
+    // The error happened in the value of `key`, errors are built from
+    // the faulty value up to the root of the file
+    pub(crate) fn in_key(mut self, key: &str) -> Self {
+        self.key_path.insert(0, PathSegment::Key(key.to_owned()));
+        self
+    }
+
+    // The error happened in the element `index` of an array
+    pub(crate) fn in_index(mut self, index: usize) -> Self {
+        self.key_path.insert(0, PathSegment::Index(index));
+        self
+    }
+
+    // The error happened in the value at `key_path`
+    pub(crate) fn in_key_path(self, key_path: &[PathSegment]) -> Self {
+        key_path
+            .iter()
+            .rev()
+            .fold(self, |error, segment| match segment {
+                PathSegment::Key(key) => error.in_key(key),
+                PathSegment::Index(index) => error.in_index(*index),
+            })
+    }
+
+    pub(crate) fn key_path_segments(&self) -> &[PathSegment] {
+        &self.key_path
+    }
+
+    pub(crate) fn in_file(mut self, file: &Path) -> Self {
+        self.file.get_or_insert_with(|| file.to_path_buf());
+        self
+    }
+
+    pub(crate) fn at(mut self, location: Location) -> Self {
+        self.location = Some(location);
+        self
+    }
+
+    // Find the location of the faulty key with a format specific function
+    pub(crate) fn locate_with(
+        mut self,
+        locate: impl FnOnce(&[PathSegment]) -> Option<Location>,
+    ) -> Self {
+        if self.location.is_none() && !self.key_path.is_empty() {
+            self.location = locate(&self.key_path);
+        }
+        self
+    }
+}
+
+// Write a key path as `a.g[1].h`
+pub(crate) fn format_key_path(key_path: &[PathSegment]) -> String {
+    let mut res = String::new();
+    for segment in key_path.iter() {
+        match segment {
+            PathSegment::Key(key) if res.is_empty() => res.push_str(key),
+            PathSegment::Key(key) => res.push_str(&format!(".{key}")),
+            PathSegment::Index(index) => res.push_str(&format!("[{index}]")),
+        }
+    }
+    res
+}
+
+impl Location {
+    // Compute the line and column of a byte offset in `contents`
+    pub(crate) fn from_offset(contents: &str, offset: usize) -> Self {
+        let before = &contents[..offset.min(contents.len())];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        let column = before[line_start..].chars().count() + 1;
+        Location { line, column }
+    }
+}
+
+// The starts of the lines of a text, to compute the locations of many offsets
+// without counting the lines before each of them
+pub(crate) struct LineStarts<'a> {
+    contents: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> LineStarts<'a> {
+    pub(crate) fn new(contents: &'a str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(contents.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        LineStarts { contents, starts }
+    }
+
+    // Same as `Location::from_offset`
+    pub(crate) fn location(&self, offset: usize) -> Location {
+        let offset = offset.min(self.contents.len());
+        let line = self.starts.partition_point(|&start| start <= offset);
+        let line_start = self.starts[line - 1];
+        let column = self.contents[line_start..offset].chars().count() + 1;
+        Location { line, column }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::ReadInput(error) => write!(f, "failed to read input file: {error}"),
+            ErrorKind::WriteOutput(error) => write!(f, "failed to write output file: {error}"),
+            ErrorKind::Parse(message) => write!(f, "failed to parse input file: {message}"),
+            ErrorKind::NullValue => write!(
+                f,
+                "the type of this null value is unknown, give it one in the type map"
+            ),
+            ErrorKind::NanValue => write!(f, "NaN values are not handled"),
+            ErrorKind::InfiniteValue => write!(f, "infinite values are not handled"),
+            ErrorKind::MixedArray => write!(f, "arrays with different types are not handled"),
+            ErrorKind::IntegerOutOfRange { value, rust_type } => {
+                write!(f, "integer {value} does not fit in `{rust_type}`")
+            }
+            ErrorKind::TypeMismatch { value, rust_type } => {
+                write!(f, "value {value} does not match the type `{rust_type}`")
+            }
+            ErrorKind::InexactNumber {
+                value,
+                rust_type,
+                closest,
+            } => write!(
+                f,
+                "{value} can't be represented exactly by `{rust_type}`, it would become {closest}"
+            ),
+            ErrorKind::UnexpectedValue { value, allowed } => {
+                let allowed: Vec<String> =
+                    allowed.iter().map(|value| format!("{value:?}")).collect();
+                write!(
+                    f,
+                    "{value:?} is not one of the allowed values: {}",
+                    allowed.join(", ")
+                )
+            }
+            ErrorKind::DuplicateIdentifier {
+                identifier,
+                first_key,
+            } => write!(
+                f,
+                "this key and the key `{first_key}` both become the identifier `{identifier}`"
+            ),
+            ErrorKind::InvalidOverride { variable, message } => {
+                write!(
+                    f,
+                    "invalid value for the environment variable `{variable}`: {message}"
+                )
+            }
+            ErrorKind::InvalidTypeMap(message) => write!(f, "invalid type map: {message}"),
+            ErrorKind::InvalidSchema(message) => write!(f, "invalid schema: {message}"),
+            ErrorKind::SchemaViolation(message) => {
+                write!(f, "the configuration doesn't match its schema: {message}")
+            }
+            ErrorKind::Unsupported(message) => write!(f, "{message}"),
+            ErrorKind::InvalidGenerator(message) => write!(f, "invalid generator: {message}"),
+        }
+    }
+}
+
+impl fmt::Display for ConstInitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(file) = &self.file {
+            write!(f, "\n  --> {}", file.display())?;
+            if let Some(Location { line, column }) = self.location {
+                write!(f, ":{line}:{column}")?;
+            }
+        } else if let Some(Location { line, column }) = self.location {
+            write!(f, "\n  --> {line}:{column}")?;
+        }
+        if let Some(key_path) = self.key_path() {
+            write!(f, "\n  at key `{key_path}`")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConstInitError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self.kind.as_ref() {
+            ErrorKind::ReadInput(error) | ErrorKind::WriteOutput(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+// Used by the panicking `generate_*` functions to print the error nicely
+// in the build script output
+pub(crate) fn panic_on_error(result: Result<(), ConstInitError>) {
+    if let Err(error) = result {
+        panic!("\n\nconst_init_build: {error}\n\n");
+    }
+}
@@ -0,0 +1,193 @@
+use crate::error::{ConstInitError, ErrorKind};
+use json::number::Number;
+
+/// Rust type of the constants generated from floats of the configuration file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FloatType {
+    F32,
+    #[default]
+    F64,
+}
+
+impl FloatType {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            FloatType::F32 => "f32",
+            FloatType::F64 => "f64",
+        }
+    }
+
+    // The shortest Rust literal giving back `number` in this type. Numbers which would
+    // be rounded, e.g. `0.1234567891` in `f32`, are an error instead of losing precision.
+    pub(crate) fn literal(self, number: &Number) -> Result<String, ConstInitError> {
+        let (positive, mantissa, exponent) = number.as_parts();
+        let sign = if positive { "" } else { "-" };
+        let value = format!("{sign}{mantissa}e{exponent}");
+        // `Debug` gives the shortest representation which parses back to the same float
+        let (is_finite, literal) = match self {
+            FloatType::F32 => {
+                let float: f32 = value.parse().expect("Valid float");
+                (float.is_finite(), format!("{float:?}"))
+            }
+            FloatType::F64 => {
+                let float: f64 = value.parse().expect("Valid float");
+                (float.is_finite(), format!("{float:?}"))
+            }
+        };
+        if !is_finite {
+            return Err(ConstInitError::new(ErrorKind::TypeMismatch {
+                value: number.to_string(),
+                rust_type: self.as_str().to_string(),
+            }));
+        }
+        if parse_decimal(&literal) != normalize(mantissa, exponent as i32) {
+            return Err(ConstInitError::new(ErrorKind::InexactNumber {
+                value: number.to_string(),
+                rust_type: self.as_str().to_string(),
+                closest: literal,
+            }));
+        }
+        Ok(literal)
+    }
+}
+
+// The TOML and YAML parsers read floats as `f64`, rounding the ones which don't have an exact
+// `f64` representation, e.g. `0.123456789012345678901`. Their text, e.g. `1.5e3` or `.5`, must
+// then give back the same value as the shortest literal of their `f64`, as JSON floats do.
+// The `_` separating the digits of TOML floats, e.g. `1_000.5`, are left out.
+#[cfg(any(feature = "toml", feature = "yaml"))]
+pub(crate) fn check_float_text(text: &str) -> Result<(), ConstInitError> {
+    let digits = text.replace('_', "");
+    crate::json::check_significant_digits(&digits)?;
+    let float: f64 = match digits.parse() {
+        Ok(float) if f64::is_finite(float) => float,
+        // Infinite and NaN values are reported when turning them into JSON
+        Ok(_) => return Ok(()),
+        Err(_) => {
+            return Err(ConstInitError::new(ErrorKind::Parse(format!(
+                "`{text}` is not a valid float"
+            ))));
+        }
+    };
+    let literal = format!("{float:?}");
+    if parse_decimal(&literal) != parse_decimal(&digits) {
+        return Err(ConstInitError::new(ErrorKind::InexactNumber {
+            value: text.to_string(),
+            rust_type: FloatType::F64.as_str().to_string(),
+            closest: literal,
+        }));
+    }
+    Ok(())
+}
+
+// The significant digits and the exponent of a float literal like `-1.25e-7` or `.5`,
+// the sign is ignored. The literal must have at most 19 significant digits.
+fn parse_decimal(literal: &str) -> (u64, i32) {
+    let literal = literal.trim_start_matches(['-', '+']);
+    let (digits, exponent) = match literal.split_once(['e', 'E']) {
+        Some((digits, exponent)) => (
+            digits,
+            exponent
+                .trim_start_matches('+')
+                .parse()
+                .expect("Valid exponent"),
+        ),
+        None => (literal, 0),
+    };
+    let (integral, fractional) = digits.split_once('.').unwrap_or((digits, ""));
+    let digits = format!("{integral}{fractional}");
+    let significant = digits.trim_end_matches('0');
+    let mantissa = match significant.trim_start_matches('0') {
+        "" => 0,
+        significant => significant.parse().expect("At most 19 significant digits"),
+    };
+    let trailing_zeros = (digits.len() - significant.len()) as i32;
+    normalize(
+        mantissa,
+        exponent + trailing_zeros - fractional.len() as i32,
+    )
+}
+
+// Remove the trailing zeros of the mantissa so that equal values have the same parts
+fn normalize(mut mantissa: u64, mut exponent: i32) -> (u64, i32) {
+    if mantissa == 0 {
+        return (0, 0);
+    }
+    while mantissa.is_multiple_of(10) {
+        mantissa /= 10;
+        exponent += 1;
+    }
+    (mantissa, exponent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literal(float_type: FloatType, value: &str) -> Result<String, ConstInitError> {
+        match json::parse(value).unwrap() {
+            json::JsonValue::Number(number) => float_type.literal(&number),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_float_literals() {
+        let f64_literal = |value: &str| literal(FloatType::F64, value).unwrap();
+        assert_eq!(f64_literal("3.14"), "3.14");
+        assert_eq!(f64_literal("-0.5"), "-0.5");
+        assert_eq!(f64_literal("0.1"), "0.1");
+        assert_eq!(f64_literal("3"), "3.0");
+        assert_eq!(f64_literal("1e3"), "1000.0");
+        assert_eq!(f64_literal("1.5e-7"), "1.5e-7");
+        assert_eq!(f64_literal("1e300"), "1e300");
+        assert_eq!(f64_literal("9007199254740992"), "9007199254740992.0");
+        assert_eq!(f64_literal("0.30000000000000004"), "0.30000000000000004");
+        assert_eq!(literal(FloatType::F32, "0.1").unwrap(), "0.1");
+        assert_eq!(literal(FloatType::F32, "16777216").unwrap(), "16777216.0");
+    }
+
+    #[test]
+    fn test_inexact_floats() {
+        let error = literal(FloatType::F64, "9007199254740993").unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::InexactNumber { rust_type, closest, .. }
+                if rust_type == "f64" && closest == "9007199254740992.0"
+        ));
+        assert!(literal(FloatType::F64, "0.12345678901234567").is_err());
+        assert!(literal(FloatType::F32, "3.14159265").is_err());
+        assert!(literal(FloatType::F32, "16777217").is_err());
+        assert!(matches!(
+            literal(FloatType::F32, "1e39").unwrap_err().kind(),
+            ErrorKind::TypeMismatch { .. }
+        ));
+        assert!(matches!(
+            literal(FloatType::F64, "1e400").unwrap_err().kind(),
+            ErrorKind::TypeMismatch { .. }
+        ));
+    }
+
+    #[cfg(any(feature = "toml", feature = "yaml"))]
+    #[test]
+    fn test_check_float_text() {
+        for exact in [
+            "3.0", "-0.5", ".5", "+1.5e3", "1E-7", "1e300", "100.000", "inf", "-inf", "nan",
+            "1_000.25",
+        ] {
+            assert!(check_float_text(exact).is_ok(), "{exact}");
+        }
+        let error = check_float_text("0.12345678901234567").unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::InexactNumber { closest, .. } if closest == "0.12345678901234566"
+        ));
+        let error = check_float_text("1_000.123_456_789_012_34").unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::InexactNumber { closest, .. } if closest == "1000.1234567890124"
+        ));
+        let error = check_float_text("0.123456789012345678901").unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::Unsupported(_)));
+    }
+}
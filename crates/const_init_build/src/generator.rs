@@ -0,0 +1,1573 @@
+use crate::cfg::{cfg_instructions, is_cfg_prefix};
+use crate::docs::{Docs, take_doc_annotations};
+use crate::enums::{EnumType, Enums};
+use crate::env::apply_env_overrides;
+use crate::error::{ConstInitError, ErrorKind, Location, PathSegment};
+use crate::float::FloatType;
+use crate::integer::{Integer, IntegerType};
+use crate::json::{json_key_locations, locate_json_key, parse_json};
+use crate::json5::{json5_key_locations, locate_json5_key, parse_json5};
+use crate::merge::{ArrayMerge, contains, merge};
+use crate::naming::{Namespace, Naming, Scope, identifier};
+use crate::schema::Schema;
+use crate::sections::select_sections;
+use crate::structs::{Structs, is_array_of_objects};
+use crate::types::{RustType, TypeMap, has_mixed_numbers, has_null};
+use json::JsonValue;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Builder used in build scripts to generate a rust file from a configuration file.
+/// The output file contains all the variables from the input file as rust constants.
+///
+/// The `generate_constants_from_*` functions are shortcuts for a [`Generator`]
+/// with its default options.
+///
+/// # Example
+///
+/// ```rust,no_run,file:build.rs
+/// use std::path::PathBuf;
+/// use const_init_build::{Generator, Visibility};
+/// fn main() {
+///     let manifest_path = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+///     let json_input: PathBuf = [&manifest_path, "settings.json"].iter().collect();
+///     let rust_output: PathBuf = [&manifest_path, "examples", "generated", "settings.rs"]
+///         .iter()
+///         .collect();
+///
+///     Generator::new()
+///         .input(&json_input)
+///         .output(&rust_output)
+///         // The constants are only visible in the current crate
+///         .visibility(Visibility::Crate)
+///         .run()
+///         .unwrap();
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Generator {
+    input: Option<PathBuf>,
+    overlays: Vec<PathBuf>,
+    array_merge: ArrayMerge,
+    output: Option<Output>,
+    format: Option<Format>,
+    visibility: Visibility,
+    header: Option<String>,
+    rerun_if_changed: bool,
+    integer_type: IntegerType,
+    float_type: FloatType,
+    naming: Naming,
+    tuples: bool,
+    structs: bool,
+    types: Option<PathBuf>,
+    env_prefix: Option<String>,
+    cfg_prefix: Option<String>,
+    cfg_values: Vec<String>,
+    schema: Option<PathBuf>,
+    // Loaded from `types` when running the generator
+    type_map: TypeMap,
+    // Collected from the configuration files when running the generator
+    docs: Docs,
+    // Taken from the `$enum` annotations when running the generator
+    enums: Enums,
+}
+
+// The locations of keys in a configuration file, by key path
+pub(crate) type KeyLocations = HashMap<Vec<PathSegment>, Location>;
+
+// A configuration file merged with the other ones
+pub(crate) struct Layer<'a> {
+    pub(crate) file: &'a Path,
+    pub(crate) format: Format,
+    pub(crate) contents: String,
+    pub(crate) json: JsonValue,
+}
+
+// Where the generated rust file is written
+#[derive(Debug, Clone)]
+enum Output {
+    File(PathBuf),
+    // A file named `{name}.rs` in the `OUT_DIR` of the build script
+    OutDir(String),
+}
+
+/// Format of the input configuration file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Format {
+    Json,
+    /// JSON with comments, trailing commas and the other additions of JSON5,
+    /// see [`generate_constants_from_json5`](crate::generate_constants_from_json5)
+    Json5,
+    #[cfg(feature = "toml")]
+    Toml,
+    #[cfg(feature = "yaml")]
+    Yaml,
+}
+
+/// Visibility of the generated modules and constants
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Visibility {
+    /// `pub`, the default
+    #[default]
+    Public,
+    /// `pub(crate)`
+    Crate,
+}
+
+impl Format {
+    /// Guess the format of a file from its extension
+    pub fn from_path(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()? {
+            "json" => Some(Format::Json),
+            "json5" | "jsonc" => Some(Format::Json5),
+            #[cfg(feature = "toml")]
+            "toml" => Some(Format::Toml),
+            #[cfg(feature = "yaml")]
+            "yaml" | "yml" => Some(Format::Yaml),
+            _ => None,
+        }
+    }
+
+    // Every supported file format is turned into a `JsonValue`
+    // before generating constants
+    fn parse(self, contents: &str) -> Result<JsonValue, ConstInitError> {
+        match self {
+            Format::Json => parse_json(contents),
+            Format::Json5 => parse_json5(contents),
+            #[cfg(feature = "toml")]
+            Format::Toml => crate::toml::parse_toml(contents),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => crate::yaml::parse_yaml(contents),
+        }
+    }
+
+    // Find the line and column of a key
+    pub(crate) fn locate(self, contents: &str, key_path: &[PathSegment]) -> Option<Location> {
+        match self {
+            Format::Json => locate_json_key(contents, key_path),
+            Format::Json5 => locate_json5_key(contents, key_path),
+            #[cfg(feature = "toml")]
+            Format::Toml => crate::toml::locate_toml_key(contents, key_path),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => crate::yaml::locate_yaml_key(contents, key_path),
+        }
+    }
+
+    // The locations of all the keys of the objects which are not in arrays, to locate
+    // many keys without going through the whole file for each of them
+    pub(crate) fn key_locations(self, contents: &str) -> KeyLocations {
+        match self {
+            Format::Json => json_key_locations(contents),
+            Format::Json5 => json5_key_locations(contents),
+            #[cfg(feature = "toml")]
+            Format::Toml => crate::toml::toml_key_locations(contents),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => crate::yaml::yaml_key_locations(contents),
+        }
+    }
+
+    // The start of the line comments of the format, JSON has no comments
+    pub(crate) fn line_comment(self) -> Option<&'static str> {
+        match self {
+            Format::Json => None,
+            Format::Json5 => Some("//"),
+            #[cfg(feature = "toml")]
+            Format::Toml => Some("#"),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => Some("#"),
+        }
+    }
+}
+
+impl Visibility {
+    fn as_str(self) -> &'static str {
+        match self {
+            Visibility::Public => "pub",
+            Visibility::Crate => "pub(crate)",
+        }
+    }
+}
+
+const INDENT: &str = "\t";
+
+impl Generator {
+    /// A generator with the default options: `pub` constants, `isize` integers, `f64` floats,
+    /// [`Naming::SnakeCase`] names and the format guessed from the extension of the input
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The configuration file to read the values from
+    pub fn input<P: AsRef<Path>>(mut self, input_file: P) -> Self {
+        self.input = Some(input_file.as_ref().to_path_buf());
+        self
+    }
+
+    /// A configuration file merged on top of the input file, e.g. the settings of a user
+    /// overriding some of the default settings. Overlays are merged in the order they are
+    /// given, each one on top of the previous ones: objects are merged key by key and the
+    /// other values replace the ones below them, arrays are merged with [`Generator::array_merge`].
+    ///
+    /// ```rust,no_run,file:build.rs
+    /// fn main() {
+    ///     const_init_build::Generator::new()
+    ///         .input("default_settings.json")
+    ///         .overlay("user_settings.json")
+    ///         .output_to_out_dir("settings")
+    ///         .run()
+    ///         .unwrap();
+    /// }
+    /// ```
+    pub fn overlay<P: AsRef<Path>>(mut self, overlay_file: P) -> Self {
+        self.overlays.push(overlay_file.as_ref().to_path_buf());
+        self
+    }
+
+    /// Let environment variables override single values at build time, e.g.
+    /// `CONST_INIT__SERVER__PORT=9000 cargo build` with the prefix `CONST_INIT`.
+    ///
+    /// The name of a variable is the prefix followed by the names of the modules and of the
+    /// constant in uppercase, separated by `__`. The elements of arrays of objects are named
+    /// after their index, e.g. `CONST_INIT__SERVERS__0__PORT`. The value of a variable is parsed
+    /// according to the type of the value it overrides: strings are taken as they are and
+    /// the other values are written in JSON.
+    ///
+    /// Generating the constants fails when a variable with the prefix doesn't match any value.
+    /// `cargo:rerun-if-env-changed` is printed for every variable which can override a value,
+    /// this also enables [`Generator::rerun_if_changed`] so that the input files are still
+    /// watched.
+    pub fn env_prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.env_prefix = Some(prefix.into());
+        self.rerun_if_changed = true;
+        self
+    }
+
+    /// Set cargo cfgs from the settings, so that whole items can be compiled out with
+    /// `#[cfg(...)]` depending on the configuration: `cargo:rustc-cfg=const_init_foo` is
+    /// printed for every true boolean, e.g. `"foo": true` with the prefix `const_init`.
+    ///
+    /// The name of a cfg is the prefix followed by the names of the modules and of the
+    /// constant in snake case, separated by `_`, e.g. `const_init_server_tls`. All the
+    /// booleans are declared with `cargo:rustc-check-cfg`, so that `#[cfg(not(...))]` can
+    /// be used for the false ones. See [`Generator::cfg_value`] for string values.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run,file:build.rs
+    /// fn main() {
+    ///     const_init_build::Generator::new()
+    ///         .input("settings.json")
+    ///         .output_to_out_dir("settings")
+    ///         .cfg_prefix("const_init")
+    ///         .cfg_value("mode")
+    ///         .run()
+    ///         .unwrap();
+    /// }
+    /// ```
+    ///
+    /// _main.rs:_
+    /// ```rust,ignore
+    /// #[cfg(const_init_telemetry)]
+    /// mod telemetry;
+    ///
+    /// #[cfg(const_init_mode = "fast")]
+    /// fn process() {}
+    /// ```
+    ///
+    /// Generating the constants fails when `prefix` is not a valid identifier or when two
+    /// keys give the same cfg, e.g. `a.b_c` and `a_b.c`.
+    pub fn cfg_prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.cfg_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Set a cfg with the string value at `key`, e.g. `const_init_mode="fast"` for the key
+    /// `mode` with the value `"fast"` and the prefix given to [`Generator::cfg_prefix`].
+    /// The keys of nested objects are separated by `.`, e.g. `server.mode`.
+    ///
+    /// Generating the constants fails when the key is missing or its value is not
+    /// a string on a single line.
+    pub fn cfg_value<S: Into<String>>(mut self, key: S) -> Self {
+        self.cfg_values.push(key.into());
+        self
+    }
+
+    /// How the arrays of the overlays are merged, [`ArrayMerge::Replace`] by default
+    pub fn array_merge(mut self, array_merge: ArrayMerge) -> Self {
+        self.array_merge = array_merge;
+        self
+    }
+
+    /// The rust file to write the constants into
+    pub fn output<P: AsRef<Path>>(mut self, output_rust_file: P) -> Self {
+        self.output = Some(Output::File(output_rust_file.as_ref().to_path_buf()));
+        self
+    }
+
+    /// Write the constants into `$OUT_DIR/{name}.rs` instead of a file of your source tree,
+    /// it is then included in your crate with `const_init::include_settings!("{name}")`.
+    ///
+    /// This also enables [`Generator::rerun_if_changed`].
+    ///
+    /// ```rust,no_run,file:build.rs
+    /// fn main() {
+    ///     const_init_build::Generator::new()
+    ///         .input("settings.json")
+    ///         .output_to_out_dir("settings")
+    ///         .run()
+    ///         .unwrap();
+    /// }
+    /// ```
+    ///
+    /// ```rust,ignore,file:src/main.rs
+    /// mod settings {
+    ///     const_init::include_settings!("settings");
+    /// }
+    /// ```
+    pub fn output_to_out_dir<S: Into<String>>(mut self, name: S) -> Self {
+        self.output = Some(Output::OutDir(name.into()));
+        self.rerun_if_changed = true;
+        self
+    }
+
+    /// Print `cargo:rerun-if-changed` for every file read by the generator so that
+    /// the build script only runs again when one of them changes
+    pub fn rerun_if_changed(mut self, enabled: bool) -> Self {
+        self.rerun_if_changed = enabled;
+        self
+    }
+
+    /// Format of the input file and of the overlays, guessed from their extension when
+    /// not specified
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Visibility of the generated modules and constants, `pub` by default
+    pub fn visibility(mut self, visibility: Visibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    /// Rust type of the constants generated from integers, `isize` by default.
+    ///
+    /// Generating the constants fails if one of the integers doesn't fit in this type.
+    /// With [`IntegerType::Smallest`] each constant gets the smallest type fitting its value.
+    ///
+    /// Integers are read with at most 64 bits of significant digits: `i128` and `u128` hold
+    /// values such as `1e30` or `-15e20`, but an integer like `u128::MAX` is rejected since
+    /// it can't be read exactly.
+    pub fn integer_type(mut self, integer_type: IntegerType) -> Self {
+        self.integer_type = integer_type;
+        self
+    }
+
+    /// Rust type of the constants generated from floats, `f64` by default.
+    ///
+    /// Floats are written with the shortest literal giving back their value. Generating the
+    /// constants fails if a value can't be represented exactly in this type, e.g. `0.1234567891`
+    /// in `f32` or `9007199254740993` in an array of `f64`, instead of silently rounding it.
+    pub fn float_type(mut self, float_type: FloatType) -> Self {
+        self.float_type = float_type;
+        self
+    }
+
+    /// How the keys of the input file are turned into the names of the constants, modules
+    /// and structs, [`Naming::SnakeCase`] by default: `fontSize` gives `FONT_SIZE`
+    pub fn naming(mut self, naming: Naming) -> Self {
+        self.naming = naming;
+        self
+    }
+
+    /// Turn the arrays whose elements have different types, such as `["host", 8080, true]`,
+    /// into tuples, e.g. `(&str, isize, bool)`. Such arrays are an error by default.
+    ///
+    /// Arrays mixing integers and floats don't need it, they always become arrays of `f64`.
+    pub fn tuples(mut self, enabled: bool) -> Self {
+        self.tuples = enabled;
+        self
+    }
+
+    /// Turn every object but the root into a struct and a constant of this struct,
+    /// instead of a module containing one constant per key.
+    ///
+    /// For example `"a": { "b": [1, 2, -3], "c": { "d": "ding!" } }` becomes:
+    /// ```rust
+    /// #[derive(Debug, Clone, Copy, PartialEq)]
+    /// pub struct AC {
+    ///     pub d: &'static str,
+    /// }
+    /// #[derive(Debug, Clone, Copy, PartialEq)]
+    /// pub struct A {
+    ///     pub b: [isize; 3],
+    ///     pub c: AC,
+    /// }
+    /// pub const A: A = A { b: [1, 2, -3], c: AC { d: "ding!" } };
+    /// ```
+    /// Null values, which have no other value to get their type from, need a type in
+    /// the type map (see [`Generator::types`]).
+    pub fn structs(mut self, enabled: bool) -> Self {
+        self.structs = enabled;
+        self
+    }
+
+    /// A type map giving the Rust type of some values of the input file instead of
+    /// the type inferred from the values, its format is guessed from its extension.
+    ///
+    /// The keys of the type map follow the ones of the input file and their values are
+    /// the names of the Rust types: integer types, `f32`, `f64`, `bool`, `&str`,
+    /// `&'static str`, `char` or `Option` of one of them. For arrays, it is the type of
+    /// their elements. Null values get an `Option` of their type, e.g. `Option<&str>`.
+    /// Generating the constants fails if a value doesn't fit in its type
+    /// or if a key of the type map is not in the input file.
+    ///
+    /// _settings.types.toml:_
+    /// ```toml
+    /// server.port = "u16"
+    /// server.ratio = "f32"
+    /// separator = "char"
+    /// proxy = "&str"
+    /// ```
+    pub fn types<P: AsRef<Path>>(mut self, types_file: P) -> Self {
+        self.types = Some(types_file.as_ref().to_path_buf());
+        self
+    }
+
+    /// A [JSON Schema](https://json-schema.org) the configuration must match, e.g. the
+    /// one used by editors to complete the input file. Its format is guessed from its
+    /// extension.
+    ///
+    /// Every value which doesn't match the schema is reported with a `cargo:warning`
+    /// and generating the constants fails. The keywords checked are `type`, `enum`,
+    /// `const`, `required`, `properties`, `additionalProperties`, `items`, `allOf`,
+    /// `minimum`, `maximum`, `exclusiveMinimum`, `exclusiveMaximum`, `minLength`,
+    /// `maxLength`, `pattern`, `minItems` and `maxItems`, along with the `$ref`s to the
+    /// schema itself such as `#/$defs/port`. The other keywords are ignored.
+    ///
+    /// The schema also gives the Rust types of the values, unless the type map
+    /// (see [`Generator::types`]) gives another one: `number`s are floats even when
+    /// written as integers, an `integer` with a `minimum` and a `maximum` gets the
+    /// smallest integer type fitting both, e.g. `u16` for `0` and `65535`, a nullable
+    /// type such as `["string", "null"]` gives the type of null values, and strings with
+    /// an `enum` of allowed values become Rust enums as with `$enum` annotations.
+    pub fn schema<P: AsRef<Path>>(mut self, schema_file: P) -> Self {
+        self.schema = Some(schema_file.as_ref().to_path_buf());
+        self
+    }
+
+    /// Comment written at the top of the generated file instead of the default one,
+    /// each line of `header` is turned into a `//` comment
+    pub fn header<S: Into<String>>(mut self, header: S) -> Self {
+        self.header = Some(header.into());
+        self
+    }
+
+    /// Read the input file and write the output rust file containing the constants.
+    ///
+    /// The constants and modules are documented with the comments or `$doc` annotations
+    /// of their keys, and with the file and line defining them.
+    ///
+    /// The output only depends on the content of the input files: their paths are written
+    /// relative to the crate in the header and the documentation, and the file is not written
+    /// again when its content doesn't change, so the crates using it are not recompiled
+    /// for nothing.
+    pub fn run(&self) -> Result<(), ConstInitError> {
+        let input_file = self
+            .input
+            .as_deref()
+            .ok_or_else(|| invalid_generator("no input file was given"))?;
+        let output_rust_file = match &self.output {
+            Some(Output::File(output_rust_file)) => output_rust_file.clone(),
+            Some(Output::OutDir(name)) => {
+                let out_dir = std::env::var_os("OUT_DIR").ok_or_else(|| {
+                    invalid_generator(
+                        "OUT_DIR is not set, the generator must run in a build script",
+                    )
+                })?;
+                Path::new(&out_dir).join(format!("{name}.rs"))
+            }
+            None => return Err(invalid_generator("no output file was given")),
+        };
+        if let Some(prefix) = &self.cfg_prefix
+            && !is_cfg_prefix(prefix)
+        {
+            return Err(invalid_generator(&format!(
+                "the cfg prefix `{prefix}` is not a valid identifier"
+            )));
+        }
+        let files: Vec<&Path> = std::iter::once(input_file)
+            .chain(self.overlays.iter().map(PathBuf::as_path))
+            .collect();
+        let mut layers = Vec::new();
+        for file in files.iter().copied() {
+            let format = match self.format {
+                Some(format) => format,
+                None => Format::from_path(file).ok_or_else(|| {
+                    invalid_generator(
+                        "the format of the input file can't be guessed from its extension",
+                    )
+                })?,
+            };
+            let contents = self.read_input(file)?;
+            let json = format
+                .parse(&contents)
+                .map_err(|error| error.in_file(file))?;
+            layers.push(Layer {
+                file,
+                format,
+                contents,
+                json,
+            });
+        }
+        let mut json = layers[0].json.clone();
+        for layer in &layers[1..] {
+            merge(&mut json, layer.json.clone(), self.array_merge);
+        }
+
+        select_sections(&mut json, self.array_merge, &|name| {
+            std::env::var(name).ok()
+        })
+        .map_err(|error| self.locate_in_layers(error, &layers))?;
+        let annotations = take_doc_annotations(&mut json)
+            .map_err(|error| self.locate_in_layers(error, &layers))?;
+        let docs = Docs::collect(&json, &annotations, &layers);
+        let mut enums = Enums::take_annotations(&mut json)
+            .map_err(|error| self.locate_in_layers(error, &layers))?;
+        if let Some(prefix) = &self.env_prefix {
+            let names = std::env::vars_os().filter_map(|(name, _)| name.into_string().ok());
+            let variables = apply_env_overrides(
+                &mut json,
+                prefix,
+                self.naming,
+                &|name| std::env::var(name).ok(),
+                names,
+            )
+            .map_err(|error| self.locate_in_layers(error, &layers))?;
+            for variable in variables {
+                println!("cargo:rerun-if-env-changed={variable}");
+            }
+        }
+        let schema = match &self.schema {
+            Some(schema_file) => {
+                let schema = self.read_schema(schema_file)?;
+                // Every violation is reported before failing on the first one
+                let violations: Vec<ConstInitError> = schema
+                    .validate(&json)
+                    .into_iter()
+                    .map(|violation| self.locate_in_layers(violation, &layers))
+                    .collect();
+                for violation in &violations {
+                    let message = violation.to_string();
+                    let lines: Vec<&str> = message.lines().map(str::trim).collect();
+                    println!("cargo:warning={}", lines.join(" "));
+                }
+                if let Some(violation) = violations.into_iter().next() {
+                    return Err(violation);
+                }
+                enums.extend(schema.enums());
+                Some(schema)
+            }
+            None => None,
+        };
+        match &self.cfg_prefix {
+            Some(prefix) => {
+                let instructions =
+                    cfg_instructions(&json, prefix, &self.cfg_values, &enums, self.naming)
+                        .map_err(|error| self.locate_in_layers(error, &layers))?;
+                for instruction in instructions {
+                    println!("{instruction}");
+                }
+            }
+            None if !self.cfg_values.is_empty() => {
+                return Err(invalid_generator(
+                    "a cfg value was given without a prefix for the cfgs",
+                ));
+            }
+            None => (),
+        }
+
+        let mut generator = match &self.types {
+            Some(types_file) => self.with_type_map(types_file, &json)?,
+            None => self.clone(),
+        };
+        if let Some(schema) = &schema {
+            let types = schema.types(self.integer_type, self.float_type);
+            generator.type_map.extend(types);
+        }
+        generator.docs = docs;
+        generator.enums = enums;
+        let generated_content = generator
+            .generate(&files, &json)
+            .map_err(|error| self.locate_in_layers(error, &layers))?;
+
+        // The file is only written when its content changes, so that its modification time
+        // doesn't make cargo recompile the crates using it for nothing
+        let existing_content = std::fs::read(&output_rust_file).ok();
+        if existing_content.as_deref() == Some(generated_content.as_bytes()) {
+            return Ok(());
+        }
+        std::fs::write(&output_rust_file, generated_content).map_err(|error| {
+            ConstInitError::new(ErrorKind::WriteOutput(error)).in_file(&output_rust_file)
+        })
+    }
+
+    // The faulty value comes from the last file defining it. The indices of appended
+    // arrays don't match the ones of the files, only their key is located then.
+    fn locate_in_layers(&self, error: ConstInitError, layers: &[Layer]) -> ConstInitError {
+        let key_path = match self.array_merge {
+            ArrayMerge::Replace => error.key_path_segments(),
+            ArrayMerge::Append => {
+                let segments = error.key_path_segments();
+                let keys = segments
+                    .iter()
+                    .take_while(|segment| matches!(segment, PathSegment::Key(_)))
+                    .count();
+                &segments[..keys]
+            }
+        }
+        .to_vec();
+        let layer = layers
+            .iter()
+            .rev()
+            .find(|layer| contains(&layer.json, &key_path))
+            .unwrap_or(&layers[0]);
+        error
+            .in_file(layer.file)
+            .locate_with(|_| layer.format.locate(&layer.contents, &key_path))
+    }
+
+    // A copy of the generator using the types of `types_file` for the values of `json`
+    fn with_type_map(&self, types_file: &Path, json: &JsonValue) -> Result<Self, ConstInitError> {
+        let format = Format::from_path(types_file).ok_or_else(|| {
+            invalid_generator("the format of the type map can't be guessed from its extension")
+        })?;
+        let contents = self.read_input(types_file)?;
+        let type_map = format
+            .parse(&contents)
+            .and_then(|types| TypeMap::from_json(&types))
+            .and_then(|type_map| type_map.check_keys(json).map(|()| type_map))
+            .map_err(|error| {
+                error
+                    .in_file(types_file)
+                    .locate_with(|key_path| format.locate(&contents, key_path))
+            })?;
+        Ok(Generator {
+            type_map,
+            ..self.clone()
+        })
+    }
+
+    // The schema of `schema_file`, whose patterns and references are checked
+    fn read_schema(&self, schema_file: &Path) -> Result<Schema, ConstInitError> {
+        let format = Format::from_path(schema_file).ok_or_else(|| {
+            invalid_generator("the format of the schema can't be guessed from its extension")
+        })?;
+        let contents = self.read_input(schema_file)?;
+        format
+            .parse(&contents)
+            .and_then(Schema::from_json)
+            .map_err(|error| {
+                error
+                    .in_file(schema_file)
+                    .locate_with(|key_path| format.locate(&contents, key_path))
+            })
+    }
+
+    // Every file read by the generator goes through here
+    fn read_input(&self, input_file: &Path) -> Result<String, ConstInitError> {
+        if self.rerun_if_changed {
+            println!("cargo:rerun-if-changed={}", input_file.display());
+        }
+        std::fs::read_to_string(input_file)
+            .map_err(|error| ConstInitError::new(ErrorKind::ReadInput(error)).in_file(input_file))
+    }
+
+    // Produce the content of the output rust file containing constants
+    fn generate(&self, input_files: &[&Path], json: &JsonValue) -> Result<String, ConstInitError> {
+        let mut generated_content = String::new();
+        // Files included with `include!` can't have inner attributes,
+        // `include_settings!` takes care of them instead
+        if !matches!(self.output, Some(Output::OutDir(_))) {
+            generated_content.push_str("#![allow(dead_code)]\n");
+            generated_content.push_str("#![allow(clippy::all)]\n");
+        }
+        match &self.header {
+            Some(header) => {
+                for line in header.lines() {
+                    match line {
+                        "" => generated_content.push_str("//\n"),
+                        line => generated_content.push_str(&format!("// {line}\n")),
+                    }
+                }
+            }
+            None => {
+                generated_content.push_str("// Generated file, don't modify it\n");
+                match input_files {
+                    [input_file] => generated_content.push_str(&format!(
+                        "// This file is built at compile-time and contains the variable from \"{}\"\n",
+                        display_path(input_file)
+                    )),
+                    _ => {
+                        generated_content.push_str(
+                            "// This file is built at compile-time and contains the variables merged from:\n",
+                        );
+                        for input_file in input_files {
+                            generated_content.push_str(&format!(
+                                "// - \"{}\"\n",
+                                display_path(input_file)
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        // The `$doc` annotation of the root describes the whole file
+        if let Some(doc) = self.docs.get(&[]) {
+            generated_content.push_str("//\n");
+            for line in doc.lines() {
+                match line {
+                    "" => generated_content.push_str("//\n"),
+                    line => generated_content.push_str(&format!("// {line}\n")),
+                }
+            }
+        }
+        generated_content.push('\n');
+        self.json_to_constants(&mut generated_content, json, &[])?;
+        Ok(generated_content)
+    }
+
+    // Turn a json object to rust constants, `key_path` leads from the root
+    // of the configuration file to `json` and is empty for the root itself.
+    // Returns the identifiers of the items written in `content`.
+    pub(crate) fn json_to_constants(
+        &self,
+        content: &mut String,
+        json: &JsonValue,
+        key_path: &[PathSegment],
+    ) -> Result<Vec<(Namespace, String)>, ConstInitError> {
+        // The content of the root object is not wrapped in a module
+        let recursion_depth = key_path.len().saturating_sub(1);
+        let spacing = INDENT.repeat(recursion_depth);
+        let visibility = self.visibility.as_str();
+        let field_name = key_path.last().map(|segment| match segment {
+            PathSegment::Key(key) => key.to_owned(),
+            PathSegment::Index(index) => format!("_{index}"),
+        });
+        let mut items = Vec::new();
+        let generated = match (json, self.enums.get(key_path)) {
+            // Arrays of objects (e.g. TOML arrays of tables) become arrays of structs,
+            // and objects other than the root become structs with `structs` enabled
+            _ if is_array_of_objects(json)
+                || (json.is_object() && self.structs && !key_path.is_empty()) =>
+            {
+                let name = field_name.ok_or_else(root_is_not_an_object)?;
+                let mut structs = Structs {
+                    type_map: &self.type_map,
+                    enums: &self.enums,
+                    integer_type: self.integer_type,
+                    float_type: self.float_type,
+                    naming: self.naming,
+                    visibility,
+                    key_path,
+                    names: Scope::default(),
+                    definitions: Vec::new(),
+                };
+                let (var_type, value) = structs.constant(&name, json)?;
+                let mut res = String::new();
+                for (struct_name, definition) in structs.definitions {
+                    for line in definition.lines() {
+                        res.push_str(&format!("{spacing}{line}\n"));
+                    }
+                    items.push((Namespace::Type, struct_name));
+                }
+                let name = identifier(&self.naming.constant(&name));
+                res.push_str(&self.doc_comment(key_path, &spacing));
+                res.push_str(&format!(
+                    "{spacing}{visibility} const {name}: {var_type} = {value};\n"
+                ));
+                items.push((Namespace::Value, name));
+                res
+            }
+            (JsonValue::Object(object), _) => {
+                let mut res = String::new();
+                if let Some(ref name) = field_name {
+                    // If this is not the initial object of the json file
+                    let name = identifier(&self.naming.module(name));
+                    res.push_str(&self.doc_comment(key_path, &spacing));
+                    res.push_str(&format!("{}{} mod {} {{\n", spacing, visibility, name));
+                    items.push((Namespace::Type, name));
+                }
+                let mut scope = Scope::default();
+                for (name, value) in object.iter() {
+                    let key_path = [key_path, &[PathSegment::Key(name.to_owned())]].concat();
+                    for (namespace, identifier) in self
+                        .json_to_constants(&mut res, value, &key_path)
+                        .map_err(|error| error.in_key(name))?
+                    {
+                        scope
+                            .declare(namespace, &identifier, &key_path)
+                            .map_err(|error| error.in_key(name))?;
+                    }
+                }
+
+                if field_name.is_some() {
+                    // If this is not the initial object of the json file
+                    res.push_str(&format!("{}}}\n", spacing));
+                }
+                res
+            }
+            // Strings with a fixed set of allowed values become enums
+            (_, Some(values)) => {
+                let key = field_name.ok_or_else(root_is_not_an_object)?;
+                let enum_name = identifier(&self.naming.type_name(&key));
+                let enum_type = EnumType::new(enum_name, values, self.naming)?;
+                let value = enum_type.literal(json)?;
+                let mut res = String::new();
+                for line in enum_type.definition(visibility).lines() {
+                    res.push_str(&format!("{spacing}{line}\n"));
+                }
+                let name = identifier(&self.naming.constant(&key));
+                res.push_str(&self.doc_comment(key_path, &spacing));
+                res.push_str(&format!(
+                    "{spacing}{visibility} const {name}: {} = {value};\n",
+                    enum_type.name
+                ));
+                items.push((Namespace::Type, enum_type.name));
+                items.push((Namespace::Value, name));
+                res
+            }
+            _ => {
+                let name = identifier(
+                    &self
+                        .naming
+                        .constant(&field_name.ok_or_else(root_is_not_an_object)?),
+                );
+                let (var_type, value) = match self.type_map.get(key_path) {
+                    // The type map decides the type, the value must fit in it
+                    Some(rust_type) => rust_type.type_and_value(json)?,
+                    // Null values take the type of the other values of their array,
+                    // integers mixed with floats are turned into floats
+                    None if has_null(json) || has_mixed_numbers(json) => {
+                        match RustType::infer([json], self.integer_type, self.float_type)? {
+                            Some(rust_type) => rust_type.type_and_value(json)?,
+                            None => return Err(ConstInitError::new(ErrorKind::NullValue)),
+                        }
+                    }
+                    None => {
+                        // All the integers of a constant share the same Rust type
+                        let mut integers = Vec::new();
+                        collect_integers(json, &mut integers)?;
+                        let integer_type = self.integer_type.resolve(&integers)?;
+                        json_to_rust(json, integer_type, self.float_type, self.tuples)?
+                    }
+                };
+                let mut res = self.doc_comment(key_path, &spacing);
+                res.push_str(&format!(
+                    "{spacing}{visibility} const {name}: {var_type} = {value};\n"
+                ));
+                items.push((Namespace::Value, name));
+                res
+            }
+        };
+        content.push_str(&generated);
+        Ok(items)
+    }
+
+    // The documentation of the item generated for `key_path` as `///` comments
+    fn doc_comment(&self, key_path: &[PathSegment], spacing: &str) -> String {
+        let mut res = String::new();
+        for line in self.docs.get(key_path).into_iter().flat_map(str::lines) {
+            match line {
+                "" => res.push_str(&format!("{spacing}///\n")),
+                line => res.push_str(&format!("{spacing}/// {line}\n")),
+            }
+        }
+        res
+    }
+}
+
+// The path of an input file written in the header. Files of the crate are written relative
+// to its manifest, with `/` separators, so that the generated file doesn't depend on where
+// the crate is built.
+pub(crate) fn display_path(path: &Path) -> String {
+    let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from);
+    let path = manifest_dir
+        .and_then(|manifest_dir| path.strip_prefix(manifest_dir).ok())
+        .unwrap_or(path);
+    match path.is_relative() {
+        true => path
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+        false => path.to_string_lossy().into_owned(),
+    }
+}
+
+fn invalid_generator(message: &str) -> ConstInitError {
+    ConstInitError::new(ErrorKind::InvalidGenerator(message.to_string()))
+}
+
+fn root_is_not_an_object() -> ConstInitError {
+    ConstInitError::new(ErrorKind::Unsupported(
+        "the root of the configuration file must be an object".to_string(),
+    ))
+}
+
+// Returns the Rust type and the Rust literal of a value. Arrays whose elements have
+// different types are tuples when `tuples` is enabled, otherwise they are an error.
+fn json_to_rust(
+    json: &JsonValue,
+    integer_type: IntegerType,
+    float_type: FloatType,
+    tuples: bool,
+) -> Result<(String, String), ConstInitError> {
+    let rust = match json {
+        JsonValue::Null => return Err(ConstInitError::new(ErrorKind::NullValue)),
+        JsonValue::Short(_) | JsonValue::String(_) => (
+            "&str".to_string(),
+            string_literal(json.as_str().unwrap_or_default()),
+        ),
+        JsonValue::Number(number) => {
+            if number.is_nan() {
+                return Err(ConstInitError::new(ErrorKind::NanValue));
+            }
+            match Integer::from_number(number) {
+                Some(integer) => (integer_type.as_str().to_string(), integer?.to_string()),
+                None => (float_type.as_str().to_string(), float_type.literal(number)?),
+            }
+        }
+        JsonValue::Boolean(_) => ("bool".to_string(), json.to_string()),
+        JsonValue::Array(json_values) => {
+            let len = json_values.len();
+            if len == 0 {
+                return Ok((format!("[{}; 0]", integer_type.as_str()), "[]".to_string()));
+            }
+            let elements = json_values
+                .iter()
+                .enumerate()
+                .map(|(index, v)| {
+                    json_to_rust(v, integer_type, float_type, tuples)
+                        .map_err(|error| error.in_index(index))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let (types, values): (Vec<String>, Vec<String>) = elements.into_iter().unzip();
+            let first_type = &types[0];
+            if types.iter().all(|json_type| json_type == first_type) {
+                (
+                    format!("[{first_type}; {len}]"),
+                    format!("[{}]", values.join(",")),
+                )
+            } else if tuples {
+                (
+                    format!("({})", types.join(", ")),
+                    format!("({})", values.join(",")),
+                )
+            } else {
+                return Err(ConstInitError::new(ErrorKind::MixedArray));
+            }
+        }
+        JsonValue::Object(_) => {
+            return Err(ConstInitError::new(ErrorKind::Unsupported(
+                "objects inside arrays of other values are not handled".to_string(),
+            )));
+        }
+    };
+    Ok(rust)
+}
+
+// Write a string as a Rust literal which gives back the same string. Multi-line values are
+// raw strings to stay readable, unless they contain characters which must be escaped.
+pub(crate) fn string_literal(value: &str) -> String {
+    if value.contains('\n') && !value.chars().any(|char| char != '\n' && must_escape(char)) {
+        // The smallest number of `#` which doesn't appear after a `"` in the value
+        let hashes = (1..)
+            .map(|count| "#".repeat(count))
+            .find(|hashes| !value.contains(&format!("\"{hashes}")))
+            .unwrap_or_default();
+        return format!("r{hashes}\"{value}\"{hashes}");
+    }
+    let mut literal = String::with_capacity(value.len() + 2);
+    literal.push('"');
+    for char in value.chars() {
+        match char {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            '\0' => literal.push_str("\\0"),
+            char if must_escape(char) => literal.push_str(&format!("\\u{{{:x}}}", char as u32)),
+            char => literal.push(char),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+// Characters which can't be written as they are in a raw string: the control characters
+// (a bare `\r` is rejected by the compiler) and the ones changing the direction of the text,
+// which are denied by the `text_direction_codepoint_in_literal` lint. Tabs are kept.
+fn must_escape(char: char) -> bool {
+    (char.is_control() && char != '\t')
+        || matches!(char, '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+        || matches!(char, '\u{200e}' | '\u{200f}' | '\u{061c}' | '\u{feff}')
+}
+
+// Gather the integers of a value, including the ones inside arrays
+fn collect_integers(json: &JsonValue, integers: &mut Vec<Integer>) -> Result<(), ConstInitError> {
+    match json {
+        JsonValue::Number(number) => {
+            if let Some(integer) = Integer::from_number(number) {
+                integers.push(integer?);
+            }
+        }
+        JsonValue::Array(json_values) => {
+            for (index, value) in json_values.iter().enumerate() {
+                collect_integers(value, integers).map_err(|error| error.in_index(index))?;
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Empty directory for the files of a test, unique to the test and to the process so that
+    // concurrent test runs don't share files
+    fn test_directory(test: &str) -> std::path::PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("const_init_build_{test}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn test_visibility_and_header() {
+        let parsed = json::parse(r#"{ "a": { "b": 1 }, "c": "d" }"#).unwrap();
+        let generated = Generator::new()
+            .visibility(Visibility::Crate)
+            .header("My settings\n\nDon't touch")
+            .generate(&[Path::new("settings.json")], &parsed)
+            .unwrap();
+
+        let expected = "#![allow(dead_code)]
+#![allow(clippy::all)]
+// My settings
+//
+// Don't touch
+
+pub(crate) mod a {
+\tpub(crate) const B: isize = 1;
+}
+pub(crate) const C: &str = \"d\";
+";
+        assert_eq!(generated, expected)
+    }
+
+    #[test]
+    fn test_string_literal() {
+        assert_eq!(string_literal(r#"a "b" \c"#), r#""a \"b\" \\c""#);
+        assert_eq!(string_literal("a\tb\r\0"), r#""a\tb\r\0""#);
+        assert_eq!(
+            string_literal("\u{1b}[0m \u{202e}"),
+            r#""\u{1b}[0m \u{202e}""#
+        );
+        assert_eq!(string_literal("日本 😀"), r#""日本 😀""#);
+        // Multi-line values are raw strings, unless they contain characters to escape
+        assert_eq!(string_literal("a\n\tb"), "r#\"a\n\tb\"#");
+        assert_eq!(string_literal("\"#\n"), "r##\"\"#\n\"##");
+        assert_eq!(string_literal("a\r\nb"), r#""a\r\nb""#);
+    }
+
+    #[test]
+    fn test_integer_types() {
+        let parsed = json::parse(
+            r#"{ "a": 1, "b": -300, "c": [1, 70000], "d": 18446744073709551615, "e": 1e3 }"#,
+        )
+        .unwrap();
+        let mut generated = String::new();
+        Generator::new()
+            .integer_type(IntegerType::Smallest)
+            .json_to_constants(&mut generated, &parsed, &[])
+            .unwrap();
+
+        let expected = "pub const A: u8 = 1;
+pub const B: i16 = -300;
+pub const C: [u32; 2] = [1,70000];
+pub const D: u64 = 18446744073709551615;
+pub const E: u16 = 1000;
+";
+        assert_eq!(generated, expected);
+
+        let mut generated = String::new();
+        let error = Generator::new()
+            .integer_type(IntegerType::I64)
+            .json_to_constants(&mut generated, &parsed, &[])
+            .unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::IntegerOutOfRange { .. }));
+        assert_eq!(error.key_path().as_deref(), Some("d"));
+
+        // Integers wider than 64 bits are read when their significant digits fit in a `u64`
+        let parsed = crate::json::parse_json(r#"{ "a": 2e30, "b": -1.5e30 }"#).unwrap();
+        let mut generated = String::new();
+        Generator::new()
+            .integer_type(IntegerType::Smallest)
+            .json_to_constants(&mut generated, &parsed, &[])
+            .unwrap();
+        let expected = "pub const A: u128 = 2000000000000000000000000000000;
+pub const B: i128 = -1500000000000000000000000000000;
+";
+        assert_eq!(generated, expected);
+        let error = Generator::new()
+            .json_to_constants(&mut String::new(), &parsed, &[])
+            .unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::IntegerOutOfRange { rust_type, .. } if rust_type == "isize"
+        ));
+        let error =
+            crate::json::parse_json(r#"{ "max": 170141183460469231731687303715884105727 }"#)
+                .unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::Unsupported(_)));
+    }
+
+    #[test]
+    fn test_structs() {
+        let parsed = json::parse(
+            r#"{ "foo": true, "a": { "b": [1, 2, -3], "c": { "d": "ding!" } }, "e": [{ "f": 1 }] }"#,
+        )
+        .unwrap();
+        let mut generated = String::new();
+        Generator::new()
+            .structs(true)
+            .json_to_constants(&mut generated, &parsed, &[])
+            .unwrap();
+
+        let expected = "pub const FOO: bool = true;
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AC {
+\tpub d: &'static str,
+}
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct A {
+\tpub b: [isize; 3],
+\tpub c: AC,
+}
+pub const A: A = A { b: [1,2,-3], c: AC { d: \"ding!\" } };
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct E {
+\tpub f: isize,
+}
+pub const E: [E; 1] = [E { f: 1 }];
+";
+        assert_eq!(generated, expected);
+    }
+
+    #[test]
+    fn test_run_with_type_map() {
+        let directory = test_directory("run_with_type_map");
+        let input_file = directory.join("settings.json");
+        let types_file = directory.join("settings.types.json");
+        let output_file = directory.join("settings.rs");
+        std::fs::write(
+            &input_file,
+            r#"{ "server": { "port": 8080, "ratio": 0.5 }, "separator": ";" }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &types_file,
+            r#"{ "server": { "port": "u16", "ratio": "f32" }, "separator": "char" }"#,
+        )
+        .unwrap();
+
+        let generator = Generator::new()
+            .input(&input_file)
+            .output(&output_file)
+            .header("")
+            .types(&types_file);
+        generator.run().unwrap();
+        // The documentation of the constants is checked by `test_run_with_docs`
+        let generated: String = std::fs::read_to_string(&output_file)
+            .unwrap()
+            .lines()
+            .filter(|line| !line.trim_start().starts_with("///"))
+            .map(|line| format!("{line}\n"))
+            .collect();
+        assert!(generated.ends_with(
+            "pub mod server {
+\tpub const PORT: u16 = 8080;
+\tpub const RATIO: f32 = 0.5;
+}
+pub const SEPARATOR: char = ';';
+"
+        ));
+
+        std::fs::write(&types_file, r#"{ "server": { "port": "i8" } }"#).unwrap();
+        let error = generator.run().unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::IntegerOutOfRange { .. }));
+        assert_eq!(error.key_path().as_deref(), Some("server.port"));
+        assert_eq!(error.file(), Some(input_file.as_path()));
+
+        std::fs::write(&types_file, r#"{ "server": { "host": "&str" } }"#).unwrap();
+        let error = generator.run().unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::InvalidTypeMap(_)));
+        assert_eq!(error.file(), Some(types_file.as_path()));
+        assert_eq!(
+            error.location(),
+            Some(Location {
+                line: 1,
+                column: 15
+            })
+        );
+    }
+
+    #[test]
+    fn test_run_with_overlays() {
+        let directory = test_directory("run_with_overlays");
+        let defaults_file = directory.join("default_settings.json");
+        let user_file = directory.join("user_settings.json");
+        let output_file = directory.join("settings.rs");
+        std::fs::write(
+            &defaults_file,
+            r#"{ "theme": { "font": "mono", "size": 12 }, "plugins": ["git"] }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &user_file,
+            r#"{ "theme": { "size": 14 }, "plugins": ["vim"] }"#,
+        )
+        .unwrap();
+
+        let generator = Generator::new()
+            .input(&defaults_file)
+            .overlay(&user_file)
+            .output(&output_file);
+        generator.clone().run().unwrap();
+        let generated = std::fs::read_to_string(&output_file).unwrap();
+        let expected_header = format!(
+            "// This file is built at compile-time and contains the variables merged from:
+// - \"{}\"
+// - \"{}\"
+",
+            defaults_file.display(),
+            user_file.display()
+        );
+        assert!(generated.contains(&expected_header), "{generated}");
+        assert!(generated.contains("pub const FONT: &str = \"mono\";"));
+        assert!(generated.contains("pub const SIZE: isize = 14;"));
+        assert!(generated.contains("pub const PLUGINS: [&str; 1] = [\"vim\"];"));
+
+        generator
+            .clone()
+            .array_merge(ArrayMerge::Append)
+            .run()
+            .unwrap();
+        let generated = std::fs::read_to_string(&output_file).unwrap();
+        assert!(generated.contains("pub const PLUGINS: [&str; 2] = [\"git\",\"vim\"];"));
+
+        // Errors are reported in the file defining the faulty value
+        std::fs::write(&user_file, "{\n  \"theme\": { \"size\": [1, \"a\"] }\n}").unwrap();
+        let error = generator.run().unwrap_err();
+        assert_eq!(error.file(), Some(user_file.as_path()));
+        assert_eq!(error.key_path().as_deref(), Some("theme.size"));
+        assert_eq!(
+            error.location(),
+            Some(Location {
+                line: 2,
+                column: 14
+            })
+        );
+    }
+
+    #[test]
+    fn test_run_only_writes_changes() {
+        let directory = test_directory("run_only_writes_changes");
+        let input_file = directory.join("settings.json");
+        let output_file = directory.join("settings.rs");
+        std::fs::write(&input_file, r#"{ "foo": true }"#).unwrap();
+        let generator = Generator::new().input(&input_file).output(&output_file);
+        let modified = || std::fs::metadata(&output_file).unwrap().modified().unwrap();
+
+        generator.clone().run().unwrap();
+        let old_time = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1000);
+        std::fs::File::options()
+            .write(true)
+            .open(&output_file)
+            .unwrap()
+            .set_modified(old_time)
+            .unwrap();
+        generator.clone().run().unwrap();
+        assert_eq!(modified(), old_time);
+
+        std::fs::write(&input_file, r#"{ "foo": false }"#).unwrap();
+        generator.run().unwrap();
+        assert_ne!(modified(), old_time);
+        let generated = std::fs::read_to_string(&output_file).unwrap();
+        assert!(generated.ends_with("pub const FOO: bool = false;\n"));
+    }
+
+    #[test]
+    fn test_run_with_docs() {
+        let directory = test_directory("run_with_docs");
+        let input_file = directory.join("settings.jsonc");
+        let output_file = directory.join("settings.rs");
+        std::fs::write(
+            &input_file,
+            r#"{
+  "$doc": "Settings of the application",
+  // Settings of the server
+  "server": {
+    /* Port it listens on,
+       between 1 and 65535 */
+    "port": 8080,
+    "$doc.timeout": "Timeout in seconds",
+    "timeout": 30
+  }
+}"#,
+        )
+        .unwrap();
+        Generator::new()
+            .input(&input_file)
+            .output(&output_file)
+            .run()
+            .unwrap();
+        let generated = std::fs::read_to_string(&output_file).unwrap();
+        let defined_in = |line| format!("Defined in `{}` at line {line}.", input_file.display());
+        assert!(generated.contains("//\n// Settings of the application\n\n"));
+        let expected = format!(
+            "/// Settings of the server
+///
+/// {}
+pub mod server {{
+\t/// Port it listens on,
+\t/// between 1 and 65535
+\t///
+\t/// {}
+\tpub const PORT: isize = 8080;
+\t/// Timeout in seconds
+\t///
+\t/// {}
+\tpub const TIMEOUT: isize = 30;
+}}
+",
+            defined_in(4),
+            defined_in(7),
+            defined_in(9)
+        );
+        assert!(generated.ends_with(&expected), "{generated}");
+    }
+
+    #[test]
+    fn test_run_with_enums() {
+        let directory = test_directory("run_with_enums");
+        let input_file = directory.join("settings.json");
+        let output_file = directory.join("settings.rs");
+        let generator = Generator::new()
+            .input(&input_file)
+            .output(&output_file)
+            .header("");
+        std::fs::write(
+            &input_file,
+            r#"{
+  "$enum.mode": ["fast", "safe"],
+  "mode": "slow"
+}"#,
+        )
+        .unwrap();
+        let error = generator.run().unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::UnexpectedValue { .. }));
+        assert_eq!(error.key_path().as_deref(), Some("mode"));
+        assert_eq!(error.location().map(|location| location.line), Some(3));
+
+        std::fs::write(
+            &input_file,
+            r#"{ "$enum.mode": ["fast", "safe"], "mode": "safe" }"#,
+        )
+        .unwrap();
+        generator.run().unwrap();
+        let generated = std::fs::read_to_string(&output_file).unwrap();
+        assert!(generated.contains("pub enum Mode {\n\tFast,\n\tSafe,\n}\n"));
+        assert!(generated.ends_with("pub const MODE: Mode = Mode::Safe;\n"));
+    }
+
+    #[test]
+    fn test_run_with_schema() {
+        let directory = test_directory("run_with_schema");
+        let input_file = directory.join("settings.json");
+        let schema_file = directory.join("settings.schema.json");
+        let output_file = directory.join("settings.rs");
+        let generator = Generator::new()
+            .input(&input_file)
+            .output(&output_file)
+            .schema(&schema_file)
+            .header("");
+        std::fs::write(
+            &schema_file,
+            r#"{
+  "type": "object",
+  "properties": {
+    "port": { "type": "integer", "minimum": 1, "maximum": 65535 },
+    "ratio": { "type": "number" },
+    "mode": { "enum": ["fast", "safe"] }
+  }
+}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &input_file,
+            r#"{
+  "port": 0,
+  "ratio": 1,
+  "mode": "fast"
+}"#,
+        )
+        .unwrap();
+        let error = generator.run().unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::SchemaViolation(_)));
+        assert_eq!(error.key_path().as_deref(), Some("port"));
+        assert_eq!(error.location().map(|location| location.line), Some(2));
+
+        std::fs::write(
+            &input_file,
+            r#"{ "port": 8080, "ratio": 1, "mode": "fast" }"#,
+        )
+        .unwrap();
+        generator.run().unwrap();
+        let generated = std::fs::read_to_string(&output_file).unwrap();
+        assert!(generated.contains("pub const PORT: u16 = 8080;\n"));
+        assert!(generated.contains("pub const RATIO: f64 = 1.0;\n"));
+        assert!(generated.contains("pub const MODE: Mode = Mode::Fast;\n"));
+
+        std::fs::write(
+            &schema_file,
+            r#"{ "properties": { "port": { "type": "port" } } }"#,
+        )
+        .unwrap();
+        let error = generator.run().unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::InvalidSchema(_)));
+        assert_eq!(error.key_path().as_deref(), Some("properties.port.type"));
+    }
+
+    #[test]
+    fn key_locations_match_the_located_keys() {
+        let files = [
+            (
+                Format::Json,
+                r#"{
+  "a": 1, "server": { "port": 80, "tls": {} },
+  "backends": [{ "name": "x" }],
+  "b": { "c": "}" }
+}"#,
+            ),
+            (
+                Format::Json5,
+                r#"{
+  // The first key
+  a: 1, 'server': { port: 80, "tls": {}, },
+  backends: [{ name: 'x' }],
+  b: { c: '}' },
+}"#,
+            ),
+            #[cfg(feature = "toml")]
+            (
+                Format::Toml,
+                r#"a = 1
+b = { c = "}" }
+
+[server]
+port = 80
+
+[server.tls]
+
+[[backends]]
+name = "x"
+"#,
+            ),
+            #[cfg(feature = "yaml")]
+            (
+                Format::Yaml,
+                r#"a: 1
+server:
+  # The port
+  port: 80
+  tls: {}
+backends:
+- name: x
+  role: y
+"b": { c: "}" }
+"#,
+            ),
+        ];
+        for (format, contents) in files {
+            let locations = format.key_locations(contents);
+            let json = format.parse(contents).unwrap();
+            let mut key_paths = Vec::new();
+            collect_key_paths(&json, &mut Vec::new(), &mut key_paths);
+            for key_path in key_paths {
+                assert_eq!(
+                    locations.get(&key_path).copied(),
+                    format.locate(contents, &key_path),
+                    "{format:?} {key_path:?}"
+                );
+            }
+            let server_port = [
+                PathSegment::Key("server".to_string()),
+                PathSegment::Key("port".to_string()),
+            ];
+            assert!(locations.contains_key(server_port.as_slice()), "{format:?}");
+        }
+
+        fn collect_key_paths(
+            json: &JsonValue,
+            key_path: &mut Vec<PathSegment>,
+            key_paths: &mut Vec<Vec<PathSegment>>,
+        ) {
+            if let JsonValue::Object(object) = json {
+                for (key, value) in object.iter() {
+                    key_path.push(PathSegment::Key(key.to_string()));
+                    key_paths.push(key_path.clone());
+                    collect_key_paths(value, key_path, key_paths);
+                    key_path.pop();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_header_paths_are_relative_to_the_manifest() {
+        let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+        let input_file = manifest_dir.join("config").join("settings.json");
+        assert_eq!(display_path(&input_file), "config/settings.json");
+        assert_eq!(display_path(Path::new("settings.json")), "settings.json");
+    }
+
+    #[test]
+    fn test_run_guesses_format_from_extension() {
+        let directory = test_directory("run_guesses_format_from_extension");
+        let input_file = directory.join("settings.json");
+        let output_file = directory.join("settings.rs");
+        std::fs::write(&input_file, r#"{ "foo": true }"#).unwrap();
+
+        Generator::new()
+            .input(&input_file)
+            .output(&output_file)
+            .run()
+            .unwrap();
+        let generated = std::fs::read_to_string(&output_file).unwrap();
+        assert!(generated.ends_with("pub const FOO: bool = true;\n"));
+
+        let error = Generator::new()
+            .input(directory.join("settings.ini"))
+            .output(&output_file)
+            .run()
+            .unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::InvalidGenerator(_)));
+    }
+}
@@ -0,0 +1,221 @@
+use crate::error::{ConstInitError, ErrorKind};
+use json::number::Number;
+
+/// Rust type of the constants generated from integers of the configuration file.
+///
+/// The integers are read with their significant digits in a `u64`, so [`IntegerType::I128`]
+/// and [`IntegerType::U128`] only widen the range to values like `1e30`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IntegerType {
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    /// The default, to stay compatible with the first versions of the crate
+    #[default]
+    Isize,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    Usize,
+    /// The smallest type fitting the value: unsigned for positive values and
+    /// signed for negative ones. For arrays, the smallest type fitting all the elements.
+    Smallest,
+}
+
+// An integer of the configuration file, kept as a sign and a magnitude
+// so that both the ranges of `i128` and `u128` are covered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Integer {
+    negative: bool,
+    magnitude: u128,
+}
+
+const SIGNED: [IntegerType; 5] = [
+    IntegerType::I8,
+    IntegerType::I16,
+    IntegerType::I32,
+    IntegerType::I64,
+    IntegerType::I128,
+];
+const UNSIGNED: [IntegerType; 5] = [
+    IntegerType::U8,
+    IntegerType::U16,
+    IntegerType::U32,
+    IntegerType::U64,
+    IntegerType::U128,
+];
+
+impl IntegerType {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            IntegerType::I8 => "i8",
+            IntegerType::I16 => "i16",
+            IntegerType::I32 => "i32",
+            IntegerType::I64 => "i64",
+            IntegerType::I128 => "i128",
+            IntegerType::Isize => "isize",
+            IntegerType::U8 => "u8",
+            IntegerType::U16 => "u16",
+            IntegerType::U32 => "u32",
+            IntegerType::U64 => "u64",
+            IntegerType::U128 => "u128",
+            IntegerType::Usize => "usize",
+            IntegerType::Smallest => "smallest integer",
+        }
+    }
+
+    // The fixed type named `name`, e.g. `u16`
+    pub(crate) fn from_name(name: &str) -> Option<IntegerType> {
+        SIGNED
+            .into_iter()
+            .chain(UNSIGNED)
+            .chain([IntegerType::Isize, IntegerType::Usize])
+            .find(|integer_type| integer_type.as_str() == name)
+    }
+
+    fn is_signed(self) -> bool {
+        matches!(
+            self,
+            IntegerType::I8
+                | IntegerType::I16
+                | IntegerType::I32
+                | IntegerType::I64
+                | IntegerType::I128
+                | IntegerType::Isize
+        )
+    }
+
+    fn bits(self) -> u32 {
+        match self {
+            IntegerType::I8 | IntegerType::U8 => 8,
+            IntegerType::I16 | IntegerType::U16 => 16,
+            IntegerType::I32 | IntegerType::U32 => 32,
+            IntegerType::I64 | IntegerType::U64 => 64,
+            IntegerType::I128 | IntegerType::U128 | IntegerType::Smallest => 128,
+            // The size of `isize` and `usize` is the one of the compilation target,
+            // not the one of the machine running the build script
+            IntegerType::Isize | IntegerType::Usize => {
+                std::env::var("CARGO_CFG_TARGET_POINTER_WIDTH")
+                    .ok()
+                    .and_then(|width| width.parse().ok())
+                    .unwrap_or(usize::BITS)
+            }
+        }
+    }
+
+    pub(crate) fn fits(self, integer: Integer) -> bool {
+        let bits = self.bits();
+        match (self.is_signed(), integer.negative) {
+            (false, true) => integer.magnitude == 0,
+            (false, false) => integer.magnitude <= u128::MAX >> (128 - bits),
+            (true, false) => integer.magnitude < 1 << (bits - 1),
+            (true, true) => integer.magnitude <= 1 << (bits - 1),
+        }
+    }
+
+    // Pick the Rust type for all the integers of a constant: `self` when it is
+    // a fixed type, which must fit all of them, or the smallest type fitting all of them
+    pub(crate) fn resolve(self, integers: &[Integer]) -> Result<IntegerType, ConstInitError> {
+        let candidates: &[IntegerType] = match self {
+            IntegerType::Smallest if integers.iter().any(|integer| integer.negative) => &SIGNED,
+            IntegerType::Smallest => &UNSIGNED,
+            _ => &[self],
+        };
+        for candidate in candidates {
+            if integers.iter().all(|integer| candidate.fits(*integer)) {
+                return Ok(*candidate);
+            }
+        }
+        // Report the first integer which doesn't fit in the widest candidate
+        let widest = candidates[candidates.len() - 1];
+        let integer = integers
+            .iter()
+            .find(|integer| !widest.fits(**integer))
+            .expect("At least one integer does not fit");
+        Err(out_of_range(integer.to_string(), widest))
+    }
+}
+
+impl Integer {
+    // Returns `None` for numbers with a fractional part, which are floats
+    pub(crate) fn from_number(number: &Number) -> Option<Result<Integer, ConstInitError>> {
+        let (positive, mantissa, exponent) = number.as_parts();
+        if exponent < 0 {
+            return None;
+        }
+        let magnitude = 10u128
+            .checked_pow(exponent as u32)
+            .and_then(|scale| (mantissa as u128).checked_mul(scale));
+        Some(match magnitude {
+            Some(magnitude) => Ok(Integer {
+                negative: !positive && magnitude != 0,
+                magnitude,
+            }),
+            None => Err(out_of_range(number.to_string(), IntegerType::U128)),
+        })
+    }
+}
+
+// Integers are always written without exponent so that they are valid Rust literals
+impl std::fmt::Display for Integer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", self.magnitude)
+    }
+}
+
+fn out_of_range(value: String, rust_type: IntegerType) -> ConstInitError {
+    ConstInitError::new(ErrorKind::IntegerOutOfRange {
+        value,
+        rust_type: rust_type.as_str().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn integers(values: &[i128]) -> Vec<Integer> {
+        values
+            .iter()
+            .map(|value| Integer {
+                negative: *value < 0,
+                magnitude: value.unsigned_abs(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_smallest_integer_type() {
+        let smallest = |values: &[i128]| IntegerType::Smallest.resolve(&integers(values)).unwrap();
+        assert_eq!(smallest(&[0, 255]), IntegerType::U8);
+        assert_eq!(smallest(&[256]), IntegerType::U16);
+        assert_eq!(smallest(&[-128, 127]), IntegerType::I8);
+        assert_eq!(smallest(&[-129]), IntegerType::I16);
+        assert_eq!(smallest(&[-1, 255]), IntegerType::I16);
+        assert_eq!(smallest(&[u64::MAX as i128]), IntegerType::U64);
+        assert_eq!(smallest(&[i64::MIN as i128 - 1]), IntegerType::I128);
+    }
+
+    #[test]
+    fn test_fixed_integer_type() {
+        let fixed =
+            |integer_type: IntegerType, values: &[i128]| integer_type.resolve(&integers(values));
+        assert_eq!(fixed(IntegerType::I32, &[-5, 5]).unwrap(), IntegerType::I32);
+        assert_eq!(fixed(IntegerType::U16, &[65535]).unwrap(), IntegerType::U16);
+
+        let error = fixed(IntegerType::U16, &[1, 65536]).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::IntegerOutOfRange { value, rust_type } if value == "65536" && rust_type == "u16"
+        ));
+        assert!(fixed(IntegerType::U64, &[-1]).is_err());
+        assert!(fixed(IntegerType::I8, &[-129]).is_err());
+    }
+}
@@ -1,3 +1,5 @@
+use crate::error::{ConstInitError, ErrorKind, LineStarts, Location, PathSegment, panic_on_error};
+use crate::generator::{Format, Generator, KeyLocations};
 use json::JsonValue;
 use std::path::Path;
 
@@ -6,7 +8,9 @@ use std::path::Path;
 ///
 /// # Panics
 ///
-/// Panics if does not find the input json file, can't parse it, or can't write the output rust file.
+/// Panics if does not find the input json file, can't parse it, can't turn one of its values
+/// into a Rust constant or can't write the output rust file.
+/// See [`try_generate_constants_from_json`] for a version returning an error instead.
 ///
 /// # Example
 ///
@@ -26,107 +30,312 @@ use std::path::Path;
 /// }
 /// ```
 pub fn generate_constants_from_json<P: AsRef<Path>>(input_json_file: P, output_rust_file: P) {
-    let contents = std::fs::read_to_string(&input_json_file).expect("Failed to read input file");
-    let json = json::parse(&contents).expect("Failed to deserialize input json file");
-
-    // Produce the content of the output rust file containing constants
-    let mut generated_content = String::new();
-    generated_content.push_str("#![allow(dead_code)]\n");
-    generated_content.push_str("// Generated file, don't modify it\n");
-    generated_content.push_str(&format!(
-        r#"// This file is built at compile-time and contains the variable from "{}""#,
-        input_json_file.as_ref().to_string_lossy()
+    panic_on_error(try_generate_constants_from_json(
+        input_json_file,
+        output_rust_file,
     ));
-    generated_content.push_str("\n\n");
-    json_to_constants(&mut generated_content, &json, 0, None);
-
-    // Generate the output file
-    std::fs::write(output_rust_file, generated_content)
-        .expect("Failed to generated Rust file for settings");
-}
-
-const INDENT: &str = "\t";
-// Turn a json object to rust constants
-fn json_to_constants(
-    content: &mut String,
-    json: &JsonValue,
-    recursion_depth: usize,
-    field_name: Option<String>,
-) {
-    let spacing = INDENT.repeat(recursion_depth);
-    let generated = match json {
-        JsonValue::Object(object) => {
-            let mut res = String::new();
-            if let Some(ref name) = field_name {
-                // If this is not the initial object of the json file
-                res.push_str(&format!("{}pub mod {} {{\n", spacing, name));
+}
+
+/// Same as [`generate_constants_from_json`] but returns a [`ConstInitError`]
+/// describing which key of the input file is at fault instead of panicking.
+///
+/// # Example
+///
+/// ```rust,no_run,file:build.rs
+/// use const_init_build::try_generate_constants_from_json;
+/// fn main() {
+///     if let Err(error) = try_generate_constants_from_json("settings.json", "settings.rs") {
+///         println!("cargo:warning={}", error.kind());
+///         std::process::exit(1);
+///     }
+/// }
+/// ```
+pub fn try_generate_constants_from_json<P: AsRef<Path>>(
+    input_json_file: P,
+    output_rust_file: P,
+) -> Result<(), ConstInitError> {
+    Generator::new()
+        .input(input_json_file)
+        .output(output_rust_file)
+        .format(Format::Json)
+        .run()
+}
+
+pub(crate) fn parse_json(contents: &str) -> Result<JsonValue, ConstInitError> {
+    let json = json::parse(contents).map_err(|error| {
+        let location = match error {
+            json::Error::UnexpectedCharacter { line, column, .. } => {
+                Some(Location { line, column })
             }
-            for (name, value) in object.iter() {
-                let mut depth = recursion_depth;
-                if field_name.is_some() {
-                    // If this is not the initial object of the json file
-                    depth += 1
+            _ => None,
+        };
+        let error = ConstInitError::new(ErrorKind::Parse(error.to_string()));
+        match location {
+            Some(location) => error.at(location),
+            None => error,
+        }
+    })?;
+    check_number_precision(contents)?;
+    Ok(json)
+}
+
+// The JSON parser keeps numbers in a `u64` mantissa and silently drops the digits which
+// don't fit, e.g. the end of `0.123456789012345678901` or of a `u128` integer.
+// Such numbers are an error rather than becoming a different value.
+fn check_number_precision(contents: &str) -> Result<(), ConstInitError> {
+    let bytes = contents.as_bytes();
+    let mut position = 0;
+    while position < bytes.len() {
+        match bytes[position] {
+            b'"' => {
+                position += 1;
+                while position < bytes.len() && bytes[position] != b'"' {
+                    position += if bytes[position] == b'\\' { 2 } else { 1 };
                 }
-                json_to_constants(&mut res, value, depth, Some(name.to_owned()));
+                position += 1;
             }
-
-            if field_name.is_some() {
-                // If this is not the initial object of the json file
-                res.push_str(&format!("{}}}\n", spacing));
+            b'-' | b'0'..=b'9' => {
+                let start = position;
+                while position < bytes.len()
+                    && matches!(
+                        bytes[position],
+                        b'-' | b'+' | b'.' | b'0'..=b'9' | b'e' | b'E'
+                    )
+                {
+                    position += 1;
+                }
+                check_significant_digits(&contents[start..position])
+                    .map_err(|error| error.at(Location::from_offset(contents, start)))?;
             }
-            res
-        }
-        JsonValue::Short(_) | JsonValue::String(_) => {
-            let name = field_name.expect("JSON file ill formatted").to_uppercase();
-            let var_type = json_to_rust_type(json);
-            format!(
-                r#"{spacing}pub const {name}: {var_type} = "{json}";{}"#,
-                "\n"
-            )
-        }
-        _ => {
-            let name = field_name.expect("JSON file ill formatted").to_uppercase();
-            let var_type = json_to_rust_type(json);
-            format!("{spacing}pub const {name}: {var_type} = {json};\n")
+            _ => position += 1,
         }
+    }
+    Ok(())
+}
+
+// Numbers whose significant digits don't fit in a `u64` can't be read exactly
+pub(crate) fn check_significant_digits(number: &str) -> Result<(), ConstInitError> {
+    let mantissa = number.split(['e', 'E']).next().unwrap_or_default();
+    let digits: String = mantissa.chars().filter(char::is_ascii_digit).collect();
+    let significant = digits.trim_start_matches('0').trim_end_matches('0');
+    if !significant.is_empty() && significant.parse::<u64>().is_err() {
+        return Err(ConstInitError::new(ErrorKind::Unsupported(format!(
+            "{number} has too many significant digits to be read exactly"
+        ))));
+    }
+    Ok(())
+}
+
+// Turn a float coming from another file format into a JSON number.
+#[cfg(any(feature = "toml", feature = "yaml"))]
+pub(crate) fn float_to_number(value: f64) -> json::number::Number {
+    as_float(json::number::Number::from(value))
+}
+
+// Floats with an integral value like `3.0` keep a negative exponent so that
+// they are still typed as `f64` and not as `isize`.
+pub(crate) fn as_float(number: json::number::Number) -> json::number::Number {
+    let (positive, mantissa, exponent) = number.as_parts();
+    if exponent < 0 {
+        return number;
+    }
+    // The JSON parser keeps the trailing zeros of `1000.0`, so the number is parsed again
+    // with a decimal digit, as long as all its digits fit in the mantissa
+    let value = 10u64
+        .checked_pow(exponent as u32 + 1)
+        .and_then(|scale| mantissa.checked_mul(scale));
+    let sign = if positive { "" } else { "-" };
+    match value.map(|value| parse_json(&format!("{sign}{}.0", value / 10))) {
+        Some(Ok(JsonValue::Number(float))) => float,
+        _ => number,
+    }
+}
+
+// Find the position of the key at `key_path` in a JSON text.
+// This is only used to report errors so it stays lenient and gives up
+// instead of failing on anything unexpected.
+pub(crate) fn locate_json_key(contents: &str, key_path: &[PathSegment]) -> Option<Location> {
+    let offset = json_key_offset(contents, key_path)?;
+    Some(Location::from_offset(contents, offset))
+}
+
+// The byte offset of the key at `key_path` in a JSON text
+pub(crate) fn json_key_offset(contents: &str, key_path: &[PathSegment]) -> Option<usize> {
+    let mut scanner = JsonScanner {
+        bytes: contents.as_bytes(),
+        position: 0,
+    };
+    scanner.find(key_path)
+}
+
+// The locations of the keys of the objects of a JSON text which are not in arrays
+pub(crate) fn json_key_locations(contents: &str) -> KeyLocations {
+    let lines = LineStarts::new(contents);
+    json_key_offsets(contents)
+        .into_iter()
+        .map(|(key_path, offset)| (key_path, lines.location(offset)))
+        .collect()
+}
+
+// The byte offsets of the keys of the objects of a JSON text which are not in arrays.
+// Like `json_key_offset`, the first of duplicate keys is kept.
+pub(crate) fn json_key_offsets(contents: &str) -> Vec<(Vec<PathSegment>, usize)> {
+    let mut scanner = JsonScanner {
+        bytes: contents.as_bytes(),
+        position: 0,
     };
-    content.push_str(&generated);
+    let mut offsets = Vec::new();
+    // The keys found before something unexpected are kept
+    let _ = scanner.keys(&mut Vec::new(), &mut offsets);
+    // When collected into a map, the last offset of a key path is kept
+    offsets.reverse();
+    offsets
+}
+
+struct JsonScanner<'a> {
+    bytes: &'a [u8],
+    position: usize,
 }
 
-fn json_to_rust_type(json: &JsonValue) -> String {
-    match json {
-        JsonValue::Null => unimplemented!("null values are not handled"),
-        JsonValue::Short(_) | JsonValue::String(_) => "&str".to_string(),
-        JsonValue::Number(v) => {
-            if v.is_nan() {
-                panic!("Nan value in input json file");
+impl<'a> JsonScanner<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+
+    fn skip_whitespaces(&mut self) {
+        while self.peek().is_some_and(|byte| byte.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: u8) -> Option<()> {
+        self.skip_whitespaces();
+        (self.peek()? == expected).then(|| self.position += 1)
+    }
+
+    // Returns the raw content of the string starting at the current position
+    fn string(&mut self) -> Option<&'a str> {
+        self.expect(b'"')?;
+        let start = self.position;
+        loop {
+            match self.peek()? {
+                b'\\' => self.position += 2,
+                b'"' => break,
+                _ => self.position += 1,
             }
-            match v.as_parts() {
-                (_, _, exponent) if exponent < 0 => "f64".to_string(),
-                _ => "isize".to_string(),
+        }
+        let res = std::str::from_utf8(&self.bytes[start..self.position]).ok();
+        self.position += 1;
+        res
+    }
+
+    fn skip_value(&mut self) -> Option<()> {
+        self.skip_whitespaces();
+        match self.peek()? {
+            b'"' => {
+                self.string()?;
+            }
+            b'{' | b'[' => {
+                let mut depth = 0;
+                loop {
+                    match self.peek()? {
+                        b'"' => {
+                            self.string()?;
+                            continue;
+                        }
+                        b'{' | b'[' => depth += 1,
+                        b'}' | b']' => depth -= 1,
+                        _ => (),
+                    }
+                    self.position += 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+            }
+            _ => {
+                while !matches!(self.peek(), None | Some(b',' | b'}' | b']')) {
+                    self.position += 1;
+                }
+            }
+        }
+        Some(())
+    }
+
+    // Collect the offsets of the keys of the value at the current position,
+    // which is at `key_path`, and of the objects it contains
+    fn keys(
+        &mut self,
+        key_path: &mut Vec<PathSegment>,
+        offsets: &mut Vec<(Vec<PathSegment>, usize)>,
+    ) -> Option<()> {
+        self.skip_whitespaces();
+        if self.peek()? != b'{' {
+            return self.skip_value();
+        }
+        self.position += 1;
+        loop {
+            self.skip_whitespaces();
+            if self.peek()? == b'}' {
+                self.position += 1;
+                return Some(());
+            }
+            let key_offset = self.position;
+            let key = self.string()?;
+            self.expect(b':')?;
+            key_path.push(PathSegment::Key(key.to_string()));
+            offsets.push((key_path.clone(), key_offset));
+            self.keys(key_path, offsets)?;
+            key_path.pop();
+            self.skip_whitespaces();
+            if self.peek()? == b',' {
+                self.position += 1;
             }
         }
-        JsonValue::Boolean(_) => "bool".to_string(),
-        JsonValue::Array(json_values) => {
-            let len = json_values.len();
-            if len == 0 {
-                return "[isize; 0]".to_string();
+    }
+
+    // Returns the offset of the key (or array element) designated by `key_path`
+    fn find(&mut self, key_path: &[PathSegment]) -> Option<usize> {
+        let (segment, rest) = key_path.split_first()?;
+        match segment {
+            PathSegment::Key(key) => {
+                self.expect(b'{')?;
+                loop {
+                    self.skip_whitespaces();
+                    let key_offset = self.position;
+                    let current_key = self.string()?;
+                    self.expect(b':')?;
+                    if current_key == key {
+                        if rest.is_empty() {
+                            return Some(key_offset);
+                        }
+                        return self.find(rest);
+                    }
+                    self.skip_value()?;
+                    self.expect(b',')?;
+                }
             }
-            let mut types = json_values.iter().map(|v| json_to_rust_type(v));
-            let first_type = types.next().unwrap();
-            let all_types_equal = types.all(|json_type| json_type == first_type);
-            if !all_types_equal {
-                panic!("JSON arrays with different types are not supported")
+            PathSegment::Index(index) => {
+                self.expect(b'[')?;
+                for _ in 0..*index {
+                    self.skip_value()?;
+                    self.expect(b',')?;
+                }
+                self.skip_whitespaces();
+                if rest.is_empty() {
+                    return Some(self.position);
+                }
+                self.find(rest)
             }
-            format!("[{first_type}; {len}]")
         }
-        JsonValue::Object(_) => unreachable!("Type conversion of json object should not be called"),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::float::FloatType;
+    use crate::naming::Naming;
 
     #[test]
     fn test_json_basic_types() {
@@ -147,7 +356,9 @@ mod tests {
         .unwrap();
 
         let mut generated = String::new();
-        json_to_constants(&mut generated, &parsed, 0, None);
+        Generator::new()
+            .json_to_constants(&mut generated, &parsed, &[])
+            .unwrap();
         // println!("{generated}");
         let generated = generated.trim();
 
@@ -192,7 +403,9 @@ pub const H: [&str; 3] = ["abc","def","hij"];
         .unwrap();
 
         let mut generated = String::new();
-        json_to_constants(&mut generated, &parsed, 0, None);
+        Generator::new()
+            .json_to_constants(&mut generated, &parsed, &[])
+            .unwrap();
         println!("{}", generated);
         let generated: String = generated.split_whitespace().collect();
 
@@ -229,7 +442,252 @@ pub mod a {
         .unwrap();
 
         let mut generated = String::new();
-        json_to_constants(&mut generated, &parsed, 0, None);
+        Generator::new()
+            .json_to_constants(&mut generated, &parsed, &[])
+            .unwrap();
+    }
+
+    #[test]
+    fn test_json_null_values() {
+        let parsed = json::parse(
+            r#"
+{
+    "a": [1, null, 3],
+    "b": [[null, "x"], ["y", "z"]],
+    "backends": [
+        { "weight": 1, "tls": null },
+        { "weight": null, "tls": true }
+    ]
+}
+"#,
+        )
+        .unwrap();
+
+        let mut generated = String::new();
+        Generator::new()
+            .json_to_constants(&mut generated, &parsed, &[])
+            .unwrap();
+        let generated: String = generated.split_whitespace().collect();
+
+        let expected: String = r#"
+pub const A: [Option<isize>; 3] = [Some(1),None,Some(3)];
+pub const B: [[Option<&str>; 2]; 2] = [[None,Some("x")],[Some("y"),Some("z")]];
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Backend {
+        pub weight: Option<isize>,
+        pub tls: Option<bool>,
+}
+pub const BACKENDS: [Backend; 2] = [
+        Backend { weight: Some(1), tls: None },
+        Backend { weight: None, tls: Some(true) }
+];
+"#
+        .split_whitespace()
+        .collect();
+        assert_eq!(generated, expected)
+    }
+
+    #[test]
+    fn test_json_arrays_of_objects() {
+        let parsed = json::parse(
+            r#"
+{
+    "backends": [
+        {
+            "name": "a",
+            "port": 80,
+            "tls": { "cert": "x" },
+            "routes": [{ "path": "/" }]
+        },
+        {
+            "name": "b",
+            "weight": 0.5,
+            "tls": { "cert": "y", "strict": true },
+            "routes": [{ "path": "/api", "limit": 3 }]
+        }
+    ]
+}
+"#,
+        )
+        .unwrap();
+
+        let mut generated = String::new();
+        Generator::new()
+            .json_to_constants(&mut generated, &parsed, &[])
+            .unwrap();
+        let generated: String = generated.split_whitespace().collect();
+
+        let expected: String = r#"
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BackendTls {
+        pub cert: &'static str,
+        pub strict: Option<bool>,
+}
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BackendRoute {
+        pub path: &'static str,
+        pub limit: Option<isize>,
+}
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Backend {
+        pub name: &'static str,
+        pub port: Option<isize>,
+        pub tls: BackendTls,
+        pub routes: [BackendRoute; 1],
+        pub weight: Option<f64>,
+}
+pub const BACKENDS: [Backend; 2] = [
+        Backend {
+                name: "a",
+                port: Some(80),
+                tls: BackendTls { cert: "x", strict: None },
+                routes: [BackendRoute { path: "/", limit: None }],
+                weight: None
+        },
+        Backend {
+                name: "b",
+                port: None,
+                tls: BackendTls { cert: "y", strict: Some(true) },
+                routes: [BackendRoute { path: "/api", limit: Some(3) }],
+                weight: Some(0.5)
+        }
+];
+"#
+        .split_whitespace()
+        .collect();
+        assert_eq!(generated, expected);
+
+        let parsed = json::parse(r#"{ "a": [{ "b": [{ "c": 1 }] }, { "b": [] }] }"#).unwrap();
+        let error = Generator::new()
+            .json_to_constants(&mut String::new(), &parsed, &[])
+            .unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::MixedArray));
+        assert_eq!(error.key_path().as_deref(), Some("a[1].b"));
+    }
+
+    #[test]
+    fn test_json_keys_are_sanitized() {
+        let parsed = json::parse(
+            r#"
+{
+    "max-connections": 10,
+    "2fa": true,
+    "type": { "mod": 1, "font size": 2 },
+    "self": [{ "use": 1 }]
+}
+"#,
+        )
+        .unwrap();
+
+        let mut generated = String::new();
+        Generator::new()
+            .json_to_constants(&mut generated, &parsed, &[])
+            .unwrap();
+
+        let expected = "pub const MAX_CONNECTIONS: isize = 10;
+pub const _2FA: bool = true;
+pub mod r#type {
+\tpub const MOD: isize = 1;
+\tpub const FONT_SIZE: isize = 2;
+}
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Self_ {
+\tpub r#use: isize,
+}
+pub const SELF: [Self_; 1] = [Self_ { r#use: 1 }];
+";
+        assert_eq!(generated, expected);
+
+        let parsed =
+            json::parse(r#"{ "a": { "max-connections": 1, "max_connections": 2 } }"#).unwrap();
+        let error = Generator::new()
+            .json_to_constants(&mut String::new(), &parsed, &[])
+            .unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::DuplicateIdentifier { identifier, first_key }
+                if identifier == "MAX_CONNECTIONS" && first_key == "a.max-connections"
+        ));
+        assert_eq!(error.key_path().as_deref(), Some("a.max_connections"));
+
+        let parsed = json::parse(r#"{ "b": [{ "x-y": 1, "x_y": 2 }] }"#).unwrap();
+        let error = Generator::new()
+            .json_to_constants(&mut String::new(), &parsed, &[])
+            .unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::DuplicateIdentifier { .. }
+        ));
+        assert_eq!(error.key_path().as_deref(), Some("b[0].x_y"));
+    }
+
+    #[test]
+    fn test_json_naming() {
+        let parsed = json::parse(
+            r#"{ "fontSize": 12, "uiTheme": { "darkMode": true }, "HTTPServers": [{ "maxConn": 1 }] }"#,
+        )
+        .unwrap();
+
+        let mut generated = String::new();
+        Generator::new()
+            .json_to_constants(&mut generated, &parsed, &[])
+            .unwrap();
+        let expected = "pub const FONT_SIZE: isize = 12;
+pub mod ui_theme {
+\tpub const DARK_MODE: bool = true;
+}
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HttpServer {
+\tpub max_conn: isize,
+}
+pub const HTTP_SERVERS: [HttpServer; 1] = [HttpServer { max_conn: 1 }];
+";
+        assert_eq!(generated, expected);
+
+        let mut generated = String::new();
+        Generator::new()
+            .naming(Naming::Uppercase)
+            .json_to_constants(&mut generated, &parsed, &[])
+            .unwrap();
+        let expected = "pub const FONTSIZE: isize = 12;
+pub mod uiTheme {
+\tpub const DARKMODE: bool = true;
+}
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HTTPServer {
+\tpub maxConn: isize,
+}
+pub const HTTPSERVERS: [HTTPServer; 1] = [HTTPServer { maxConn: 1 }];
+";
+        assert_eq!(generated, expected);
+    }
+
+    #[test]
+    fn test_json_mixed_arrays() {
+        let parsed = json::parse(
+            r#"
+{
+    "a": [1, 2.5, 3],
+    "b": [[1, 2], [0.5, 4]],
+    "c": ["host", 8080, true],
+    "d": [["a", 1], ["b", 2]]
+}
+"#,
+        )
+        .unwrap();
+
+        let mut generated = String::new();
+        Generator::new()
+            .tuples(true)
+            .json_to_constants(&mut generated, &parsed, &[])
+            .unwrap();
+
+        let expected = r#"pub const A: [f64; 3] = [1.0,2.5,3.0];
+pub const B: [[f64; 2]; 2] = [[1.0,2.0],[0.5,4.0]];
+pub const C: (&str, isize, bool) = ("host",8080,true);
+pub const D: [(&str, isize); 2] = [("a",1),("b",2)];
+"#;
+        assert_eq!(generated, expected)
     }
 
     #[test]
@@ -246,7 +704,9 @@ pub mod a {
         .unwrap();
 
         let mut generated = String::new();
-        json_to_constants(&mut generated, &parsed, 0, None);
+        Generator::new()
+            .json_to_constants(&mut generated, &parsed, &[])
+            .unwrap();
     }
 
     #[test]
@@ -264,6 +724,115 @@ pub mod a {
         .unwrap();
 
         let mut generated = String::new();
-        json_to_constants(&mut generated, &parsed, 0, None);
+        Generator::new()
+            .json_to_constants(&mut generated, &parsed, &[])
+            .unwrap();
+    }
+
+    #[test]
+    fn json_error_has_key_path_and_location() {
+        let contents = r#"{
+    "a": {
+        "g": ["abc", "foo"],
+        "h": {
+            "i": true,
+            "j": {
+                "k": [1.5, {}]
+            }
+        }
+    }
+}"#;
+        let parsed = json::parse(contents).unwrap();
+
+        let mut generated = String::new();
+        let error = Generator::new()
+            .json_to_constants(&mut generated, &parsed, &[])
+            .unwrap_err()
+            .locate_with(|key_path| locate_json_key(contents, key_path));
+        assert!(matches!(error.kind(), ErrorKind::Unsupported(_)));
+        assert_eq!(error.key_path().as_deref(), Some("a.h.j.k[1]"));
+        assert_eq!(
+            error.location(),
+            Some(Location {
+                line: 7,
+                column: 28
+            })
+        );
+    }
+
+    #[test]
+    fn test_json_numbers() {
+        let parsed = json::parse(
+            r#"
+{
+    "a": 1e3,
+    "b": [0.1, 1.5e-7, 2.5E+2],
+    "c": 0.30000000000000004
+}
+"#,
+        )
+        .unwrap();
+
+        let mut generated = String::new();
+        Generator::new()
+            .json_to_constants(&mut generated, &parsed, &[])
+            .unwrap();
+
+        let expected = r#"pub const A: isize = 1000;
+pub const B: [f64; 3] = [0.1,1.5e-7,250.0];
+pub const C: f64 = 0.30000000000000004;
+"#;
+        assert_eq!(generated, expected);
+
+        let mut generated = String::new();
+        let error = Generator::new()
+            .float_type(FloatType::F32)
+            .json_to_constants(&mut generated, &parsed, &[])
+            .unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::InexactNumber { rust_type, .. } if rust_type == "f32"
+        ));
+        assert_eq!(error.key_path().as_deref(), Some("c"));
+    }
+
+    #[test]
+    fn json_numbers_losing_digits_are_an_error() {
+        let contents = r#"{
+    "name": "0.123456789012345678901",
+    "big": 340282366920938463463374607431768211455,
+    "long": [1.5, 0.123456789012345678901]
+}"#;
+        let error = parse_json(contents).unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::Unsupported(_)));
+        assert_eq!(
+            error.location(),
+            Some(Location {
+                line: 3,
+                column: 12
+            })
+        );
+
+        assert!(
+            parse_json(r#"{ "a": 1000000000000000000000000, "b": 0.0000000000000000000001 }"#)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_as_float() {
+        use crate::integer::Integer;
+        use json::number::Number;
+
+        for (value, float) in [(3, "3.0"), (-1000, "-1000.0"), (0, "0.0")] {
+            let number = as_float(Number::from(value));
+            assert!(Integer::from_number(&number).is_none(), "{value}");
+            assert_eq!(FloatType::F64.literal(&number).unwrap(), float);
+        }
+        let number = as_float(Number::from(0.5));
+        assert_eq!(number.as_parts(), (true, 5, -1));
+        // Numbers whose digits don't fit in the mantissa of a float stay as they are
+        let number = Number::from_parts(true, 1, 30);
+        assert_eq!(as_float(number).as_parts(), number.as_parts());
     }
 }
@@ -0,0 +1,466 @@
+use crate::error::{ConstInitError, ErrorKind, LineStarts, Location, PathSegment, panic_on_error};
+use crate::generator::{Format, Generator, KeyLocations};
+use crate::json::{json_key_offset, json_key_offsets, parse_json};
+use json::JsonValue;
+use std::path::Path;
+
+/// This function is used in build scripts to generate a rust file from a JSON5 or JSONC file,
+/// such as the settings files of editors.
+/// The output file contains all the variables from input file as rust constants,
+/// with the same layout as [`generate_constants_from_json`](crate::generate_constants_from_json).
+///
+/// On top of JSON, the following is accepted:
+/// - `//` and `/* */` comments
+/// - trailing commas in objects and arrays
+/// - unquoted keys and strings between single quotes
+/// - hexadecimal numbers, numbers with a leading `+` and with a leading or trailing `.`
+///
+/// # Panics
+///
+/// Panics if does not find the input file, can't parse it, can't turn one of its values
+/// into a Rust constant or can't write the output rust file.
+/// See [`try_generate_constants_from_json5`] for a version returning an error instead.
+///
+/// # Example
+///
+/// ```rust,no_run,file:build.rs
+/// use const_init_build::generate_constants_from_json5;
+/// fn main() {
+///     generate_constants_from_json5("settings.json5", "src/settings.rs");
+/// }
+/// ```
+pub fn generate_constants_from_json5<P: AsRef<Path>>(input_json5_file: P, output_rust_file: P) {
+    panic_on_error(try_generate_constants_from_json5(
+        input_json5_file,
+        output_rust_file,
+    ));
+}
+
+/// Same as [`generate_constants_from_json5`] but returns a [`ConstInitError`]
+/// describing which key of the input file is at fault instead of panicking.
+pub fn try_generate_constants_from_json5<P: AsRef<Path>>(
+    input_json5_file: P,
+    output_rust_file: P,
+) -> Result<(), ConstInitError> {
+    Generator::new()
+        .input(input_json5_file)
+        .output(output_rust_file)
+        .format(Format::Json5)
+        .run()
+}
+
+// JSON5 is turned into JSON then parsed as JSON, so that both produce the same constants
+pub(crate) fn parse_json5(contents: &str) -> Result<JsonValue, ConstInitError> {
+    let converted = json5_to_json(contents)?;
+    parse_json(&converted.json).map_err(|error| match error.location() {
+        Some(location) => {
+            let offset = converted.source_offset(converted.offset(location));
+            error.at(Location::from_offset(contents, offset))
+        }
+        None => error,
+    })
+}
+
+// Keys are found in the JSON text, then located in the JSON5 text
+pub(crate) fn locate_json5_key(contents: &str, key_path: &[PathSegment]) -> Option<Location> {
+    let converted = json5_to_json(contents).ok()?;
+    let offset = json_key_offset(&converted.json, key_path)?;
+    Some(Location::from_offset(
+        contents,
+        converted.source_offset(offset),
+    ))
+}
+
+// The locations of the keys of the objects of a JSON5 text which are not in arrays
+pub(crate) fn json5_key_locations(contents: &str) -> KeyLocations {
+    let Ok(converted) = json5_to_json(contents) else {
+        return KeyLocations::new();
+    };
+    let lines = LineStarts::new(contents);
+    json_key_offsets(&converted.json)
+        .into_iter()
+        .map(|(key_path, offset)| (key_path, lines.location(converted.source_offset(offset))))
+        .collect()
+}
+
+// A JSON text rewritten from a JSON5 text
+struct Converted {
+    json: String,
+    // The byte offset in `json` of each token and the index of its first char in the JSON5 text
+    tokens: Vec<(usize, usize)>,
+    // The byte offset of each char of the JSON5 text, followed by its length
+    char_offsets: Vec<usize>,
+}
+
+impl Converted {
+    // The byte offset in the JSON5 text of the byte `offset` of the JSON text.
+    // Quotes added around keys make the JSON tokens longer, the offset is the one of the start
+    // of the JSON5 token plus the distance from the start of the JSON token.
+    fn source_offset(&self, offset: usize) -> usize {
+        let offset = offset.min(self.json.len());
+        let token = self.tokens.partition_point(|&(start, _)| start <= offset);
+        let (start, char_index) = match token {
+            0 => (0, 0),
+            token => self.tokens[token - 1],
+        };
+        let char_index = char_index + self.json[start..offset].chars().count();
+        let last = self.char_offsets.len() - 1;
+        self.char_offsets[char_index.min(last)]
+    }
+
+    // The byte offset of `location` in the JSON text
+    fn offset(&self, location: Location) -> usize {
+        let line_start: usize = self
+            .json
+            .split_inclusive('\n')
+            .take(location.line.saturating_sub(1))
+            .map(str::len)
+            .sum();
+        let line = &self.json[line_start..];
+        line_start
+            + line
+                .char_indices()
+                .nth(location.column.saturating_sub(1))
+                .map_or(line.len(), |(offset, _)| offset)
+    }
+}
+
+// Rewrite a JSON5 text as JSON. Comments are replaced with spaces so that the values
+// stay on the same line, and the start of each token is kept to locate it in the JSON5 text.
+fn json5_to_json(contents: &str) -> Result<Converted, ConstInitError> {
+    let chars: Vec<char> = contents.chars().collect();
+    let mut json = String::with_capacity(contents.len());
+    let mut tokens = Vec::new();
+    let mut position = 0;
+    let error_at = |kind: ErrorKind, position: usize| {
+        ConstInitError::new(kind).at(Location::from_offset(
+            contents,
+            byte_offset(&chars, position),
+        ))
+    };
+    let parse_error = |message: &str| ErrorKind::Parse(message.to_string());
+    while position < chars.len() {
+        tokens.push((json.len(), position));
+        match chars[position] {
+            '/' if matches!(chars.get(position + 1), Some('/' | '*')) => {
+                position = skip_comment(&chars, position, &mut json)
+                    .ok_or_else(|| error_at(parse_error("unterminated comment"), position))?;
+            }
+            // Trailing commas are dropped
+            ',' if matches!(next_token(&chars, position + 1), Some(']' | '}')) => position += 1,
+            quote @ ('"' | '\'') => {
+                let start = position;
+                json.push('"');
+                position += 1;
+                loop {
+                    match chars.get(position) {
+                        None => return Err(error_at(parse_error("unterminated string"), start)),
+                        Some(&char) if char == quote => break,
+                        Some('\\') => {
+                            position = escape(&chars, position, &mut json).ok_or_else(|| {
+                                error_at(parse_error("invalid escape sequence"), position)
+                            })?;
+                            continue;
+                        }
+                        Some('"') => json.push_str("\\\""),
+                        Some(&char) => json.push(char),
+                    }
+                    position += 1;
+                }
+                json.push('"');
+                position += 1;
+            }
+            '+' | '-' | '.' | '0'..='9' => {
+                let start = position;
+                while chars
+                    .get(position)
+                    .is_some_and(|char| char.is_alphanumeric() || matches!(char, '+' | '-' | '.'))
+                {
+                    // Only the sign of the exponent follows a letter
+                    if matches!(chars[position], '+' | '-')
+                        && position > start
+                        && !matches!(chars[position - 1], 'e' | 'E')
+                    {
+                        break;
+                    }
+                    position += 1;
+                }
+                let number: String = chars[start..position].iter().collect();
+                json.push_str(&number_to_json(&number).map_err(|kind| error_at(kind, start))?);
+            }
+            char if is_identifier_start(char) => {
+                let start = position;
+                while chars
+                    .get(position)
+                    .is_some_and(|char| is_identifier_part(*char))
+                {
+                    position += 1;
+                }
+                let identifier: String = chars[start..position].iter().collect();
+                match identifier.as_str() {
+                    // Unquoted keys, which can also be `true` or `null`
+                    _ if next_token(&chars, position) == Some(':') => {
+                        json.push_str(&format!("\"{identifier}\""));
+                    }
+                    "true" | "false" | "null" => json.push_str(&identifier),
+                    "NaN" => return Err(error_at(ErrorKind::NanValue, start)),
+                    "Infinity" => return Err(error_at(ErrorKind::InfiniteValue, start)),
+                    _ => {
+                        let message = format!("unexpected `{identifier}`, strings must be quoted");
+                        return Err(error_at(parse_error(&message), start));
+                    }
+                }
+            }
+            // JSON5 accepts more whitespaces than JSON, e.g. non-breaking spaces
+            char if char.is_whitespace() && !char.is_ascii_whitespace() => {
+                json.push(' ');
+                position += 1;
+            }
+            char => {
+                json.push(char);
+                position += 1;
+            }
+        }
+    }
+    let char_offsets = contents
+        .char_indices()
+        .map(|(offset, _)| offset)
+        .chain([contents.len()])
+        .collect();
+    Ok(Converted {
+        json,
+        tokens,
+        char_offsets,
+    })
+}
+
+// Skip the comment starting at `position`, writing a space for each of its characters
+// except new lines, and return the position following it
+fn skip_comment(chars: &[char], position: usize, json: &mut String) -> Option<usize> {
+    let end = match chars[position + 1] {
+        '/' => (position..chars.len())
+            .find(|&index| chars[index] == '\n')
+            .unwrap_or(chars.len()),
+        _ => {
+            (position + 2..chars.len().saturating_sub(1))
+                .find(|&index| chars[index] == '*' && chars[index + 1] == '/')?
+                + 2
+        }
+    };
+    for char in &chars[position..end] {
+        json.push(if *char == '\n' { '\n' } else { ' ' });
+    }
+    Some(end)
+}
+
+// The first character after `position` which is not a whitespace or in a comment
+fn next_token(chars: &[char], mut position: usize) -> Option<char> {
+    loop {
+        match chars.get(position)? {
+            char if char.is_whitespace() => position += 1,
+            '/' if matches!(chars.get(position + 1), Some('/' | '*')) => {
+                position = skip_comment(chars, position, &mut String::new())?;
+            }
+            char => return Some(*char),
+        }
+    }
+}
+
+// Write the JSON equivalent of the escape sequence at `position` in a string
+// and return the position following it
+fn escape(chars: &[char], position: usize, json: &mut String) -> Option<usize> {
+    let next = *chars.get(position + 1)?;
+    let mut end = position + 2;
+    match next {
+        '"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't' => json.extend(['\\', next]),
+        '\'' => json.push('\''),
+        'v' => json.push_str("\\u000b"),
+        '0' if !chars.get(end).is_some_and(char::is_ascii_digit) => json.push_str("\\u0000"),
+        'x' | 'u' => {
+            let len = if next == 'x' { 2 } else { 4 };
+            let digits: String = chars.get(end..end + len)?.iter().collect();
+            u32::from_str_radix(&digits, 16).ok()?;
+            json.push_str(&format!("\\u{digits:0>4}"));
+            end += len;
+        }
+        // A backslash at the end of a line continues the string on the next line
+        '\n' => (),
+        '\r' => {
+            if chars.get(end) == Some(&'\n') {
+                end += 1;
+            }
+        }
+        '\u{2028}' | '\u{2029}' => (),
+        char if char.is_ascii_digit() => return None,
+        // Other escaped characters are the character itself
+        char => json.push(char),
+    }
+    Some(end)
+}
+
+// Write a JSON5 number as a JSON number, hexadecimal integers are written in decimal
+fn number_to_json(number: &str) -> Result<String, ErrorKind> {
+    let (sign, unsigned) = match number.strip_prefix(['+', '-']) {
+        Some(unsigned) if number.starts_with('-') => ("-", unsigned),
+        Some(unsigned) => ("", unsigned),
+        None => ("", number),
+    };
+    match unsigned {
+        "NaN" => return Err(ErrorKind::NanValue),
+        "Infinity" => return Err(ErrorKind::InfiniteValue),
+        _ => (),
+    }
+    if let Some(hex) = unsigned
+        .strip_prefix("0x")
+        .or_else(|| unsigned.strip_prefix("0X"))
+    {
+        let value = u128::from_str_radix(hex, 16)
+            .map_err(|_| ErrorKind::Parse("invalid hexadecimal number".to_string()))?;
+        return Ok(format!("{sign}{value}"));
+    }
+    let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+        Some(index) => unsigned.split_at(index),
+        None => (unsigned, ""),
+    };
+    let mantissa = match (mantissa.starts_with('.'), mantissa.ends_with('.')) {
+        (true, _) => format!("0{mantissa}"),
+        (_, true) => format!("{mantissa}0"),
+        _ => mantissa.to_string(),
+    };
+    Ok(format!("{sign}{mantissa}{exponent}"))
+}
+
+fn is_identifier_start(char: char) -> bool {
+    char.is_alphabetic() || matches!(char, '_' | '$')
+}
+
+fn is_identifier_part(char: char) -> bool {
+    char.is_alphanumeric() || matches!(char, '_' | '$')
+}
+
+fn byte_offset(chars: &[char], position: usize) -> usize {
+    chars[..position].iter().map(|char| char.len_utf8()).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json5_to_json() {
+        let contents = r#"{
+    // Line comment
+    unquoted: 'single "quoted"',
+    /* block
+       comment */ "trailing": [1, 2,],
+    hex: 0xFF, negative_hex: -0x10, positive: +1, leading: .5, trailing_dot: 5., exponent: 1.e3,
+    escapes: 'it\'s\x41\v',
+    $dollar_key: null,
+    null: true,
+    "url": "http://example.com", // not a comment in a string
+}"#;
+        let expected = r#"{
+            "unquoted": "single \"quoted\"",
+            "trailing": [1, 2],
+            "hex": 255, "negative_hex": -16, "positive": 1, "leading": 0.5, "trailing_dot": 5.0,
+            "exponent": 1.0e3,
+            "escapes": "it'sA\u000b",
+            "$dollar_key": null,
+            "null": true,
+            "url": "http://example.com"
+        }"#;
+        let converted = json5_to_json(contents).unwrap().json;
+        assert_eq!(
+            json::parse(&converted).unwrap(),
+            json::parse(expected).unwrap()
+        );
+        // The values stay on the same line
+        assert_eq!(converted.lines().count(), contents.lines().count());
+        assert!(
+            converted
+                .lines()
+                .nth(4)
+                .unwrap()
+                .contains(r#""trailing": [1, 2]"#)
+        );
+    }
+
+    #[test]
+    fn test_json5_same_constants_as_json() {
+        let json = r#"{ "a": { "b": [1, 2, -3], "c": 3.14, "d": "ding!" }, "e": true }"#;
+        let json5 = r#"{
+            a: { b: [1, 2, -3,], c: 3.14, d: 'ding!', }, // comment
+            e: true,
+        }"#;
+        let generate = |parsed: JsonValue| {
+            let mut generated = String::new();
+            Generator::new()
+                .json_to_constants(&mut generated, &parsed, &[])
+                .unwrap();
+            generated
+        };
+        assert_eq!(
+            generate(parse_json5(json5).unwrap()),
+            generate(parse_json(json).unwrap())
+        );
+    }
+
+    #[test]
+    fn json5_errors_have_a_location() {
+        let error = parse_json5("{\n  a: 1,\n  b: 'unterminated\n}").unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::Parse(_)));
+        assert_eq!(error.location(), Some(Location { line: 3, column: 6 }));
+
+        // Only keys can be unquoted
+        let error = parse_json5("{\n  enabled: treu,\n}").unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::Parse(message) if message.contains("treu")));
+        assert_eq!(
+            error.location(),
+            Some(Location {
+                line: 2,
+                column: 12
+            })
+        );
+        assert!(parse_json5("{ mode: fast }").is_err());
+        assert!(parse_json5("[fast]").is_err());
+
+        let error = parse_json5("{\n  a: NaN,\n}").unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::NanValue));
+        assert_eq!(error.location(), Some(Location { line: 2, column: 6 }));
+
+        let contents = "{\n  // the port\n  server: { port: 'x', },\n}";
+        let key_path = [
+            PathSegment::Key("server".to_string()),
+            PathSegment::Key("port".to_string()),
+        ];
+        assert_eq!(
+            locate_json5_key(contents, &key_path),
+            Some(Location {
+                line: 3,
+                column: 13
+            })
+        );
+        // The columns are the ones of the JSON5 text, where the keys are unquoted
+        let contents = "{ a: 1, 'b': { c: 2, d: { e: 3 } } }";
+        let key_path = [
+            PathSegment::Key("b".to_string()),
+            PathSegment::Key("d".to_string()),
+            PathSegment::Key("e".to_string()),
+        ];
+        assert_eq!(
+            locate_json5_key(contents, &key_path),
+            Some(Location {
+                line: 1,
+                column: 27
+            })
+        );
+        let error = parse_json5("{ a: 1, b: 2 3 }").unwrap_err();
+        assert_eq!(
+            error.location(),
+            Some(Location {
+                line: 1,
+                column: 14
+            })
+        );
+    }
+}
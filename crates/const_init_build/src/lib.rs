@@ -1,5 +1,5 @@
 //! Generate Rust constant variables from the data in your configuration file
-//! (currently only JSON) at build time.
+//! (JSON, TOML or YAML) at build time.
 //!
 //! The goal is to improve performance by initializing your types with build-time
 //! values and benefit from compiler optimizations.
@@ -13,7 +13,7 @@
 //! ```json,file:settings.json
 //! {
 //!   "foo": true,
-//!   "bar": 1,
+//!   "bar": 1
 //! }
 //! ```
 //!
@@ -40,20 +40,141 @@
 //! pub const BAR: isize = 1;
 //! ```
 //!
+//! # Options
+//!
+//! The `generate_constants_from_*` functions use the default options. [`Generator`] is a builder
+//! giving more control on the generated file, such as the visibility of the constants or the
+//! header of the file:
+//! ```rust,no_run,file:build.rs
+//! use const_init_build::{Generator, Visibility};
+//! fn main() {
+//!     Generator::new()
+//!         .input("settings.json")
+//!         .output("src/settings.rs")
+//!         .visibility(Visibility::Crate)
+//!         .run()
+//!         .unwrap();
+//! }
+//! ```
+//!
+//! With [`Generator::output_to_out_dir`] the file is generated in the `OUT_DIR` of the build
+//! script instead of your source tree and is included with `const_init::include_settings!`.
+//!
+//! With [`Generator::types`], a type map such as `settings.types.toml` gives the Rust type of
+//! specific values (e.g. `server.port = "u16"`) instead of the one inferred from the value.
+//!
+//! With [`Generator::overlay`], other configuration files such as the settings of a user are
+//! merged on top of the input file before generating the constants.
+//!
+//! Sections like `"$profile.release": { ... }`, `"$feature.telemetry": { ... }` or
+//! `"$target.arch.aarch64": { ... }` are merged into the object containing them when the build
+//! uses this profile, enables this cargo feature or compiles for this target (checked with the
+//! `CARGO_CFG_TARGET_*` variables), and are ignored otherwise.
+//!
+//! The comment right above a key (in JSON5, TOML and YAML files) or its `"$doc"` annotation
+//! becomes the `///` documentation of its constant or module, along with the file and line
+//! defining it. `"$doc": "..."` documents the object containing it and `"$doc.port": "..."`
+//! its key `port`. Code blocks are marked as `text` so that they don't become doctests.
+//!
+//! With [`Generator::env_prefix`], environment variables such as `CONST_INIT__SERVER__PORT`
+//! override single values at build time.
+//!
+//! With [`Generator::cfg_prefix`], true booleans set cargo cfgs such as `const_init_foo`, and
+//! [`Generator::cfg_value`] sets cfgs with string values such as `const_init_mode = "fast"`,
+//! so that whole items can be compiled out with `#[cfg(...)]`.
+//!
+//! A `"$enum.mode": ["fast", "safe"]` annotation turns the string `mode` into an enum with one
+//! variant per allowed value, and generating the constants fails when its value is not one
+//! of them.
+//!
+//! With [`Generator::schema`], the configuration is checked against a JSON Schema before
+//! generating the constants: every value which doesn't match it is reported with a
+//! `cargo:warning` and the build fails. The schema also gives the types of the constants,
+//! e.g. `u16` for an `integer` between `0` and `65535`.
+//!
+//! With [`Generator::structs`], each object of the configuration file becomes a struct and a
+//! constant of this struct instead of a module, so a whole section is a single value.
+//!
+//! # Errors
+//!
+//! The `generate_constants_from_*` functions panic with a detailed message when the
+//! constants can't be generated. Their `try_generate_constants_from_*` counterparts return
+//! a [`ConstInitError`] instead, which contains the kind of error, the path of the faulty key
+//! (e.g. `a.h.j.k`) and its line and column in the input file when they are known.
+//!
 //!# Limitations
 //!
 //!## File format
 //!
-//!Currently only JSON is supported but there are no difficulties to
-//!support other formats such as TOML.
+//!JSON is always supported, as well as JSON with comments and trailing commas (JSONC and
+//!JSON5, e.g. the settings files of editors) through [`generate_constants_from_json5`].
+//!TOML is supported with the `toml` feature through [`generate_constants_from_toml`] and
+//!YAML with the `yaml` feature through [`generate_constants_from_yaml`].
+//!Both features are enabled by default.
 //!
 //!## Json to Rust
 //!
 //!Certain JSON types do not translate perfectly into Rust types.
-//!- JSON `integers` which are not float are all turned into Rust `isize`
-//!- JSON `arrays` containing different types are not handled
-//!- JSON `null` is unsupported
+//!- JSON `integers` which are not float are turned into Rust `isize` by default,
+//!  another type can be picked with [`Generator::integer_type`]. Exponents are expanded:
+//!  `1e3` gives `1000`
+//!- JSON floats are turned into Rust `f64` by default, or `f32` with [`Generator::float_type`],
+//!  and written with the shortest literal giving back their value. Values which can't be
+//!  represented exactly in this type are an error instead of being rounded, as well as numbers
+//!  with more than 19 significant digits, in every file format
+//!- JSON `arrays` mixing integers and floats become arrays of `f64`, other arrays containing
+//!  different types are only handled as tuples with [`Generator::tuples`]
+//!- JSON `null` becomes `None` and its constant an `Option`, its type is given by the type map
+//!  ([`Generator::types`]) or by the other values of its array, e.g. `[1, null]` becomes
+//!  `[Option<isize>; 2]`
+//!- JSON `arrays` of objects become arrays of structs whose definition is generated: the fields
+//!  are the keys of all the objects, `Option`s when some objects don't have them or have
+//!  `null`, and the objects inside them become nested structs
 //!- JSON `Nan` is unsupported
+//!- JSON `strings` become `&str` literals giving back the same string: quotes, backslashes and
+//!  control characters are escaped and multi-line values are written as raw strings (`r#"..."#`)
+//!  or enums when their values are given by a `$enum` annotation
+//!- JSON keys are split into words on separators and case changes to follow the Rust naming
+//!  conventions: `fontSize` and `font-size` give the constant `FONT_SIZE`, the module
+//!  `font_size` and the struct `FontSize`. The `ConstInit` derive macro looks for the constant
+//!  of a field with the same convention. [`Naming::Uppercase`] keeps the keys as they are
+//!- JSON keys are then turned into valid Rust identifiers: characters other than ASCII letters,
+//!  digits and `_` become `_` (`max-connections` gives `MAX_CONNECTIONS`), a leading digit gets
+//!  a `_` prefix and keywords are escaped (`type` gives `r#type`). Generating the constants fails
+//!  when two keys end up with the same identifier
+
+#![allow(clippy::needless_doctest_main)]
 
+mod annotations;
+mod cfg;
+mod docs;
+mod enums;
+mod env;
+mod error;
+pub use error::*;
+mod float;
+pub use float::*;
+mod generator;
+pub use generator::*;
+mod integer;
+pub use integer::*;
 mod json;
 pub use json::*;
+mod json5;
+pub use json5::*;
+mod merge;
+pub use merge::*;
+mod naming;
+pub use naming::*;
+mod schema;
+mod sections;
+mod structs;
+#[cfg(feature = "toml")]
+mod toml;
+mod types;
+#[cfg(feature = "toml")]
+pub use toml::*;
+#[cfg(feature = "yaml")]
+mod yaml;
+#[cfg(feature = "yaml")]
+pub use yaml::*;
@@ -0,0 +1,85 @@
+use crate::error::PathSegment;
+use json::JsonValue;
+
+/// How the arrays of an overlay are merged with the arrays of the files below it,
+/// see [`Generator::overlay`](crate::Generator::overlay)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArrayMerge {
+    /// The array of the overlay replaces the one below it, the default
+    #[default]
+    Replace,
+    /// The elements of the array of the overlay are added after the ones below it
+    Append,
+}
+
+// Merge `overlay` into `base`: objects are merged key by key, the keys missing from `base`
+// are added at its end, and any other value of `overlay` replaces the one of `base`
+pub(crate) fn merge(base: &mut JsonValue, overlay: JsonValue, arrays: ArrayMerge) {
+    match (base, overlay) {
+        (JsonValue::Object(base), JsonValue::Object(overlay)) => {
+            for (key, value) in overlay.iter() {
+                match base.get_mut(key) {
+                    Some(base_value) => merge(base_value, value.clone(), arrays),
+                    None => base.insert(key, value.clone()),
+                }
+            }
+        }
+        (JsonValue::Array(base), JsonValue::Array(overlay)) if arrays == ArrayMerge::Append => {
+            base.extend(overlay);
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+// Whether `json` has a value at `key_path`, to find which file a merged value comes from
+pub(crate) fn contains(json: &JsonValue, key_path: &[PathSegment]) -> bool {
+    match key_path.split_first() {
+        None => true,
+        Some((PathSegment::Key(key), rest)) => match json {
+            JsonValue::Object(object) => object.get(key).is_some_and(|json| contains(json, rest)),
+            _ => false,
+        },
+        Some((PathSegment::Index(index), rest)) => match json {
+            JsonValue::Array(values) => values.get(*index).is_some_and(|json| contains(json, rest)),
+            _ => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge() {
+        let defaults = || {
+            json::parse(r#"{ "a": 1, "b": { "c": true, "d": [1, 2] }, "e": { "f": 1 } }"#).unwrap()
+        };
+        let overlay = json::parse(r#"{ "b": { "d": [3], "g": "x" }, "e": 2, "h": null }"#).unwrap();
+
+        let mut merged = defaults();
+        merge(&mut merged, overlay.clone(), ArrayMerge::Replace);
+        let expected = r#"{"a":1,"b":{"c":true,"d":[3],"g":"x"},"e":2,"h":null}"#;
+        assert_eq!(merged.dump(), expected);
+
+        let mut merged = defaults();
+        merge(&mut merged, overlay, ArrayMerge::Append);
+        let expected = r#"{"a":1,"b":{"c":true,"d":[1,2,3],"g":"x"},"e":2,"h":null}"#;
+        assert_eq!(merged.dump(), expected);
+    }
+
+    #[test]
+    fn test_contains() {
+        let json = json::parse(r#"{ "a": { "b": [1, { "c": 2 }] } }"#).unwrap();
+        let key = |key: &str| PathSegment::Key(key.to_string());
+        assert!(contains(
+            &json,
+            &[key("a"), key("b"), PathSegment::Index(1), key("c")]
+        ));
+        assert!(!contains(
+            &json,
+            &[key("a"), key("b"), PathSegment::Index(2)]
+        ));
+        assert!(!contains(&json, &[key("a"), key("c")]));
+    }
+}
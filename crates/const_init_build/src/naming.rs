@@ -0,0 +1,222 @@
+use crate::error::{ConstInitError, ErrorKind, PathSegment, format_key_path};
+
+/// How the keys of the configuration file are turned into Rust names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Naming {
+    /// The keys are split into words on separators (`-`, `_`, spaces, ...) and case changes,
+    /// then constants are in `SCREAMING_SNAKE_CASE`, modules and fields in `snake_case`
+    /// and structs in `PascalCase`: `fontSize` gives `FONT_SIZE` and `font_size`.
+    ///
+    /// This is the convention used by the `ConstInit` derive macro to find the constant
+    /// of a field.
+    #[default]
+    SnakeCase,
+    /// The keys are kept as they are and only uppercased for constants:
+    /// `fontSize` gives `FONTSIZE` and `fontSize`, like the first versions of the crate.
+    Uppercase,
+}
+
+impl Naming {
+    // Names are turned into valid identifiers by `identifier` afterwards
+    pub(crate) fn constant(self, key: &str) -> String {
+        match self {
+            Naming::SnakeCase => words(key).join("_").to_uppercase(),
+            Naming::Uppercase => key.to_uppercase(),
+        }
+    }
+
+    // Used for both modules and fields of structs
+    pub(crate) fn module(self, key: &str) -> String {
+        match self {
+            Naming::SnakeCase => words(key).join("_").to_lowercase(),
+            Naming::Uppercase => key.to_string(),
+        }
+    }
+
+    pub(crate) fn type_name(self, key: &str) -> String {
+        let capitalize = |word: &str| {
+            let mut chars = word.chars();
+            let first = chars.next().into_iter().flat_map(char::to_uppercase);
+            match self {
+                Naming::SnakeCase => first.chain(chars.flat_map(char::to_lowercase)).collect(),
+                Naming::Uppercase => first.chain(chars).collect::<String>(),
+            }
+        };
+        match self {
+            Naming::SnakeCase => words(key).into_iter().map(capitalize).collect(),
+            Naming::Uppercase => key
+                .split(|char: char| !char.is_alphanumeric())
+                .map(capitalize)
+                .collect(),
+        }
+    }
+}
+
+// Split a key into words on the characters which are not alphanumeric and before
+// the uppercase letters starting a word: `fontSize` and `HTTPServer` give `font Size`
+// and `HTTP Server`. The `ConstInit` derive macro splits the field names the same way.
+fn words(key: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = key.char_indices().collect();
+    let mut words = Vec::new();
+    let mut start = None;
+    for (position, &(index, char)) in chars.iter().enumerate() {
+        if !char.is_alphanumeric() {
+            if let Some(start) = start.take() {
+                words.push(&key[start..index]);
+            }
+            continue;
+        }
+        if let Some(word_start) = start
+            && char.is_uppercase()
+        {
+            let previous = chars[position - 1].1;
+            let next_is_lowercase = chars
+                .get(position + 1)
+                .is_some_and(|(_, next)| next.is_lowercase());
+            if previous.is_lowercase()
+                || previous.is_numeric()
+                || (previous.is_uppercase() && next_is_lowercase)
+            {
+                words.push(&key[word_start..index]);
+                start = Some(index);
+            }
+        }
+        start.get_or_insert(index);
+    }
+    if let Some(start) = start {
+        words.push(&key[start..]);
+    }
+    words
+}
+
+// Keywords which are valid identifiers once prefixed with `r#`
+const KEYWORDS: [&str; 48] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+// Keywords which can't be raw identifiers
+const PATH_KEYWORDS: [&str; 4] = ["crate", "self", "super", "Self"];
+
+// Turn a name built from a key of the configuration file into a valid Rust identifier.
+// Characters other than ASCII letters, digits and `_` become `_`, a leading digit is
+// prefixed with `_`, keywords are escaped with `r#` and the ones which can't be
+// escaped get a `_` suffix.
+pub(crate) fn identifier(name: &str) -> String {
+    let mut identifier: String = name
+        .chars()
+        .map(|char| match char.is_ascii_alphanumeric() {
+            true => char,
+            false => '_',
+        })
+        .collect();
+    if identifier.starts_with(|char: char| char.is_ascii_digit()) {
+        identifier.insert(0, '_');
+    }
+    match identifier.as_str() {
+        "" | "_" => "__".to_string(),
+        name if KEYWORDS.contains(&name) => format!("r#{name}"),
+        name if PATH_KEYWORDS.contains(&name) => format!("{name}_"),
+        _ => identifier,
+    }
+}
+
+// The names of the items of a module only clash with the ones of the same namespace,
+// e.g. a module `a` and a constant `a` can live together
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Namespace {
+    // Modules and structs
+    Type,
+    // Constants and fields of structs
+    Value,
+}
+
+// The identifiers declared in a module or in a struct, with the path of
+// the key each one comes from to report the keys ending up with the same identifier
+#[derive(Debug, Default)]
+pub(crate) struct Scope {
+    identifiers: Vec<(Namespace, String, Vec<PathSegment>)>,
+}
+
+impl Scope {
+    pub(crate) fn declare(
+        &mut self,
+        namespace: Namespace,
+        identifier: &str,
+        key_path: &[PathSegment],
+    ) -> Result<(), ConstInitError> {
+        let declared = self
+            .identifiers
+            .iter()
+            .find(|(declared_namespace, declared, _)| {
+                *declared_namespace == namespace && declared == identifier
+            });
+        if let Some((_, _, first_key_path)) = declared {
+            return Err(ConstInitError::new(ErrorKind::DuplicateIdentifier {
+                identifier: identifier.to_string(),
+                first_key: format_key_path(first_key_path),
+            }));
+        }
+        self.identifiers
+            .push((namespace, identifier.to_string(), key_path.to_vec()));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_naming() {
+        assert_eq!(words("fontSize"), ["font", "Size"]);
+        assert_eq!(words("HTTPServer-port"), ["HTTP", "Server", "port"]);
+        assert_eq!(words("max_connections v2"), ["max", "connections", "v2"]);
+        assert_eq!(words("v2Api"), ["v2", "Api"]);
+        assert_eq!(words("__"), Vec::<&str>::new());
+
+        assert_eq!(Naming::SnakeCase.constant("fontSize"), "FONT_SIZE");
+        assert_eq!(Naming::SnakeCase.module("fontSize"), "font_size");
+        assert_eq!(Naming::SnakeCase.type_name("tls-config"), "TlsConfig");
+        assert_eq!(Naming::SnakeCase.type_name("HTTPServer"), "HttpServer");
+        assert_eq!(Naming::Uppercase.constant("fontSize"), "FONTSIZE");
+        assert_eq!(Naming::Uppercase.module("fontSize"), "fontSize");
+        assert_eq!(Naming::Uppercase.type_name("HTTP_server"), "HTTPServer");
+    }
+
+    #[test]
+    fn test_identifier() {
+        assert_eq!(identifier("max-connections"), "max_connections");
+        assert_eq!(identifier("font size"), "font_size");
+        assert_eq!(identifier("2fa"), "_2fa");
+        assert_eq!(identifier("type"), "r#type");
+        assert_eq!(identifier("mod"), "r#mod");
+        assert_eq!(identifier("self"), "self_");
+        assert_eq!(identifier("Self"), "Self_");
+        assert_eq!(identifier("café"), "caf_");
+        assert_eq!(identifier("_"), "__");
+        assert_eq!(identifier("TYPE"), "TYPE");
+    }
+
+    #[test]
+    fn test_scope() {
+        let key_path = |key: &str| vec![PathSegment::Key(key.to_string())];
+        let mut scope = Scope::default();
+        scope
+            .declare(Namespace::Value, "A_B", &key_path("a-b"))
+            .unwrap();
+        scope
+            .declare(Namespace::Type, "A_B", &key_path("a_b"))
+            .unwrap();
+        let error = scope
+            .declare(Namespace::Value, "A_B", &key_path("a_b"))
+            .unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::DuplicateIdentifier { identifier, first_key }
+                if identifier == "A_B" && first_key == "a-b"
+        ));
+    }
+}
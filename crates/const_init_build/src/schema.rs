@@ -0,0 +1,600 @@
+use crate::annotations::Annotations;
+use crate::error::{ConstInitError, ErrorKind, PathSegment};
+use crate::float::FloatType;
+use crate::integer::{Integer, IntegerType};
+use crate::types::RustType;
+use json::JsonValue;
+use regex_lite::Regex;
+
+// A JSON Schema (https://json-schema.org) the configuration must match, such as the
+// `settings.schema.json` used by editors for autocompletion. The keywords checked are
+// `type`, `enum`, `const`, `required`, `properties`, `additionalProperties`, `items`,
+// `allOf`, `minimum`, `maximum`, `exclusiveMinimum`, `exclusiveMaximum`, `minLength`,
+// `maxLength`, `pattern`, `minItems` and `maxItems`, and the references to the schema
+// itself (`"$ref": "#/$defs/port"`) are followed. The other keywords are ignored.
+#[derive(Debug)]
+pub(crate) struct Schema {
+    root: JsonValue,
+    // The regular expressions of the `pattern` keywords
+    patterns: Vec<(String, Regex)>,
+}
+
+const TYPES: [&str; 7] = [
+    "null", "boolean", "object", "array", "number", "string", "integer",
+];
+
+// References are followed this many times in a row at most, and schemas are nested this
+// deep at most when looking for types, so that recursive schemas don't loop forever
+const MAX_DEPTH: usize = 32;
+
+// Keywords whose values are JSON values instead of schemas
+const VALUE_KEYWORDS: [&str; 4] = ["enum", "const", "default", "examples"];
+
+impl Schema {
+    pub(crate) fn from_json(root: JsonValue) -> Result<Schema, ConstInitError> {
+        let mut schema = Schema {
+            root: JsonValue::Null,
+            patterns: Vec::new(),
+        };
+        schema.check(&root, &root)?;
+        schema.root = root;
+        Ok(schema)
+    }
+
+    // Compile the patterns and check the types and the references of the schema
+    fn check(&mut self, root: &JsonValue, json: &JsonValue) -> Result<(), ConstInitError> {
+        match json {
+            JsonValue::Object(object) => {
+                for (key, value) in object.iter() {
+                    let error_in_key = |error: ConstInitError| error.in_key(key);
+                    match (key, value.as_str()) {
+                        ("pattern", Some(pattern)) => {
+                            let regex = Regex::new(pattern).map_err(|error| {
+                                error_in_key(invalid_schema(&format!("invalid pattern: {error}")))
+                            })?;
+                            self.patterns.push((pattern.to_string(), regex));
+                        }
+                        ("type", _) if !value.is_object() => {
+                            type_names(value).map_err(error_in_key)?;
+                        }
+                        ("$ref", Some(reference)) if resolve(root, reference).is_none() => {
+                            return Err(error_in_key(invalid_schema(&format!(
+                                "the reference `{reference}` is not found, only references \
+                                 to the schema itself such as `#/$defs/port` are handled"
+                            ))));
+                        }
+                        _ => (),
+                    }
+                    if !VALUE_KEYWORDS.contains(&key) {
+                        self.check(root, value).map_err(error_in_key)?;
+                    }
+                }
+            }
+            JsonValue::Array(values) => {
+                for (index, value) in values.iter().enumerate() {
+                    self.check(root, value)
+                        .map_err(|error| error.in_index(index))?;
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    // The schema `schema` refers to with `$ref`, or `schema` itself
+    fn follow_references<'a>(&'a self, mut schema: &'a JsonValue) -> &'a JsonValue {
+        for _ in 0..MAX_DEPTH {
+            match schema["$ref"]
+                .as_str()
+                .and_then(|reference| resolve(&self.root, reference))
+            {
+                Some(referenced) => schema = referenced,
+                None => break,
+            }
+        }
+        schema
+    }
+
+    // Every value of `json` which doesn't match the schema, with its key path
+    pub(crate) fn validate(&self, json: &JsonValue) -> Vec<ConstInitError> {
+        let mut violations = Vec::new();
+        self.validate_value(&self.root, json, &mut Vec::new(), &mut violations);
+        violations
+    }
+
+    fn validate_value(
+        &self,
+        schema: &JsonValue,
+        json: &JsonValue,
+        key_path: &mut Vec<PathSegment>,
+        violations: &mut Vec<ConstInitError>,
+    ) {
+        let schema = match self.follow_references(schema) {
+            JsonValue::Boolean(false) => {
+                violations.push(violation(key_path, "no value is allowed here".to_string()));
+                return;
+            }
+            schema if schema.is_object() => schema,
+            _ => return,
+        };
+        let expected = type_names(&schema["type"]).unwrap_or_default();
+        if !expected.is_empty() && !expected.iter().any(|name| has_type(json, name)) {
+            let message = format!(
+                "expected {}, found {}",
+                expected.join(" or "),
+                type_of(json)
+            );
+            violations.push(violation(key_path, message));
+            // The other keywords don't apply to values of another type
+            return;
+        }
+        let mut report = |message: String| violations.push(violation(key_path, message));
+        if let JsonValue::Array(allowed) = &schema["enum"]
+            && !allowed.contains(json)
+        {
+            let allowed: Vec<String> = allowed.iter().map(JsonValue::dump).collect();
+            report(format!(
+                "{} is not one of the allowed values: {}",
+                json.dump(),
+                allowed.join(", ")
+            ));
+        }
+        if schema.has_key("const") && schema["const"] != *json {
+            report(format!(
+                "expected {}, found {}",
+                schema["const"].dump(),
+                json.dump()
+            ));
+        }
+        match json {
+            JsonValue::Number(number) => {
+                let value = f64::from(*number);
+                // `exclusiveMinimum: true` makes `minimum` exclusive in the draft 4 of JSON Schema
+                let exclusive = |keyword: &str| schema[keyword].as_bool() == Some(true);
+                if let Some(minimum) = schema["minimum"].as_f64()
+                    && (value < minimum || (value == minimum && exclusive("exclusiveMinimum")))
+                {
+                    report(format!(
+                        "{} is less than the minimum {}",
+                        json.dump(),
+                        schema["minimum"].dump()
+                    ));
+                }
+                if let Some(maximum) = schema["maximum"].as_f64()
+                    && (value > maximum || (value == maximum && exclusive("exclusiveMaximum")))
+                {
+                    report(format!(
+                        "{} is greater than the maximum {}",
+                        json.dump(),
+                        schema["maximum"].dump()
+                    ));
+                }
+                if let Some(minimum) = schema["exclusiveMinimum"].as_f64()
+                    && value <= minimum
+                {
+                    report(format!("{} must be greater than {minimum}", json.dump()));
+                }
+                if let Some(maximum) = schema["exclusiveMaximum"].as_f64()
+                    && value >= maximum
+                {
+                    report(format!("{} must be less than {maximum}", json.dump()));
+                }
+            }
+            JsonValue::Short(_) | JsonValue::String(_) => {
+                let value = json.as_str().unwrap_or_default();
+                let length = value.chars().count();
+                if let Some(minimum) = schema["minLength"].as_usize()
+                    && length < minimum
+                {
+                    report(format!(
+                        "{} is shorter than {minimum} characters",
+                        json.dump()
+                    ));
+                }
+                if let Some(maximum) = schema["maxLength"].as_usize()
+                    && length > maximum
+                {
+                    report(format!(
+                        "{} is longer than {maximum} characters",
+                        json.dump()
+                    ));
+                }
+                if let Some(pattern) = schema["pattern"].as_str() {
+                    let regex = self.patterns.iter().find(|(current, _)| current == pattern);
+                    if regex.is_some_and(|(_, regex)| !regex.is_match(value)) {
+                        report(format!(
+                            "{} doesn't match the pattern `{pattern}`",
+                            json.dump()
+                        ));
+                    }
+                }
+            }
+            JsonValue::Array(values) => {
+                if let Some(minimum) = schema["minItems"].as_usize()
+                    && values.len() < minimum
+                {
+                    report(format!(
+                        "the array has {} elements, fewer than {minimum}",
+                        values.len()
+                    ));
+                }
+                if let Some(maximum) = schema["maxItems"].as_usize()
+                    && values.len() > maximum
+                {
+                    report(format!(
+                        "the array has {} elements, more than {maximum}",
+                        values.len()
+                    ));
+                }
+            }
+            JsonValue::Object(object) => {
+                for required in schema["required"].members().filter_map(JsonValue::as_str) {
+                    if object.get(required).is_none() {
+                        report(format!("the required key `{required}` is missing"));
+                    }
+                }
+            }
+            _ => (),
+        }
+
+        // The values inside arrays and objects
+        match json {
+            JsonValue::Array(values) => {
+                for (index, value) in values.iter().enumerate() {
+                    // Before the draft 2020-12, an array of schemas gives the schema of each element
+                    let items = match &schema["items"] {
+                        JsonValue::Array(items) => items.get(index).unwrap_or(&JsonValue::Null),
+                        items => items,
+                    };
+                    key_path.push(PathSegment::Index(index));
+                    self.validate_value(items, value, key_path, violations);
+                    key_path.pop();
+                }
+            }
+            JsonValue::Object(object) => {
+                for (key, value) in object.iter() {
+                    key_path.push(PathSegment::Key(key.to_owned()));
+                    let properties = &schema["properties"];
+                    match &schema["additionalProperties"] {
+                        _ if properties.has_key(key) => {
+                            self.validate_value(&properties[key], value, key_path, violations)
+                        }
+                        JsonValue::Boolean(false) => violations.push(violation(
+                            key_path,
+                            "this key is not allowed by the schema".to_string(),
+                        )),
+                        additional => self.validate_value(additional, value, key_path, violations),
+                    }
+                    key_path.pop();
+                }
+            }
+            _ => (),
+        }
+        for schema in schema["allOf"].members() {
+            self.validate_value(schema, json, key_path, violations);
+        }
+    }
+
+    // The Rust types given by the schema to the values of the configuration, with the keys
+    // leading to them. Only the types which differ from the inferred ones are given:
+    // `number`s are floats even when written as integers, `integer`s with a `minimum`
+    // and a `maximum` get the smallest type fitting both, and the values which can be
+    // `null` get the type of their other values.
+    pub(crate) fn types(
+        &self,
+        integer_type: IntegerType,
+        float_type: FloatType,
+    ) -> Vec<(Vec<String>, RustType)> {
+        let mut types = Vec::new();
+        self.visit(&self.root, &mut Vec::new(), 0, &mut |schema, keys| {
+            let expected = types_of_schema(schema);
+            let non_null: Vec<&str> = expected
+                .iter()
+                .copied()
+                .filter(|name| *name != "null")
+                .collect();
+            let integer = match (
+                number_to_integer(&schema["minimum"]),
+                number_to_integer(&schema["maximum"]),
+            ) {
+                (Some(minimum), Some(maximum)) => IntegerType::Smallest
+                    .resolve(&[minimum, maximum])
+                    .ok()
+                    .map(RustType::Integer),
+                _ if integer_type == IntegerType::Smallest => None,
+                _ => Some(RustType::Integer(integer_type)),
+            };
+            let float = match float_type {
+                FloatType::F32 => RustType::F32,
+                FloatType::F64 => RustType::F64,
+            };
+            let nullable = non_null.len() < expected.len();
+            let rust_type = match non_null.as_slice() {
+                ["number"] | ["integer", "number"] | ["number", "integer"] => Some(float),
+                ["integer"] => integer,
+                ["string"] if nullable => Some(RustType::Str("&str".to_string())),
+                ["boolean"] if nullable => Some(RustType::Bool),
+                _ => None,
+            };
+            if let Some(rust_type) = rust_type {
+                types.push((keys.to_vec(), rust_type));
+            }
+        });
+        types
+    }
+
+    // The allowed values of the strings whose schema has an `enum` of strings,
+    // they become enums as with the `$enum` annotations
+    pub(crate) fn enums(&self) -> Annotations<Vec<String>> {
+        let mut enums = Vec::new();
+        self.visit(&self.root, &mut Vec::new(), 0, &mut |schema, keys| {
+            let values: Option<Vec<String>> = schema["enum"]
+                .members()
+                .map(|value| value.as_str().map(str::to_string))
+                .collect();
+            if let Some(values) = values.filter(|values| !values.is_empty()) {
+                let key_path = keys
+                    .iter()
+                    .map(|key| PathSegment::Key(key.to_string()))
+                    .collect();
+                enums.push((key_path, values));
+            }
+        });
+        enums
+    }
+
+    // Call `visit` with the schema of every value of the configuration and the keys leading
+    // to it, the elements of arrays have the keys of their array
+    fn visit(
+        &self,
+        schema: &JsonValue,
+        keys: &mut Vec<String>,
+        depth: usize,
+        visit: &mut impl FnMut(&JsonValue, &[String]),
+    ) {
+        let schema = self.follow_references(schema);
+        if depth > MAX_DEPTH || !schema.is_object() {
+            return;
+        }
+        if !keys.is_empty() && !types_of_schema(schema).contains(&"array") {
+            visit(schema, keys);
+        }
+        for (key, property) in schema["properties"].entries() {
+            keys.push(key.to_string());
+            self.visit(property, keys, depth + 1, visit);
+            keys.pop();
+        }
+        self.visit(&schema["items"], keys, depth + 1, visit);
+        for schema in schema["allOf"].members() {
+            self.visit(schema, keys, depth + 1, visit);
+        }
+    }
+}
+
+// The names of the types accepted by a `type` keyword, which is a name or an array of names
+fn type_names(json: &JsonValue) -> Result<Vec<&str>, ConstInitError> {
+    let names: Vec<&JsonValue> = match json {
+        JsonValue::Null => return Ok(Vec::new()),
+        JsonValue::Array(names) => names.iter().collect(),
+        name => vec![name],
+    };
+    names
+        .into_iter()
+        .map(|name| match name.as_str() {
+            Some(name) if TYPES.contains(&name) => Ok(name),
+            _ => Err(invalid_schema(&format!(
+                "unknown type {}, expected one of {}",
+                name.dump(),
+                TYPES.join(", ")
+            ))),
+        })
+        .collect()
+}
+
+fn types_of_schema(schema: &JsonValue) -> Vec<&str> {
+    type_names(&schema["type"]).unwrap_or_default()
+}
+
+fn has_type(json: &JsonValue, name: &str) -> bool {
+    match json {
+        JsonValue::Number(number) if name == "integer" => Integer::from_number(number).is_some(),
+        _ => type_of(json) == name || (name == "number" && json.is_number()),
+    }
+}
+
+fn type_of(json: &JsonValue) -> &'static str {
+    match json {
+        JsonValue::Null => "null",
+        JsonValue::Boolean(_) => "boolean",
+        JsonValue::Number(number) if Integer::from_number(number).is_some() => "integer",
+        JsonValue::Number(_) => "number",
+        JsonValue::Short(_) | JsonValue::String(_) => "string",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "object",
+    }
+}
+
+fn number_to_integer(json: &JsonValue) -> Option<Integer> {
+    match json {
+        JsonValue::Number(number) => Integer::from_number(number)?.ok(),
+        _ => None,
+    }
+}
+
+// The value at the JSON pointer of a reference to the schema itself, e.g. `#/$defs/port`
+fn resolve<'a>(root: &'a JsonValue, reference: &str) -> Option<&'a JsonValue> {
+    let pointer = reference.strip_prefix('#')?;
+    pointer.split('/').skip(1).try_fold(root, |json, token| {
+        let token = token.replace("~1", "/").replace("~0", "~");
+        match json {
+            JsonValue::Object(_) => json.has_key(&token).then(|| &json[token.as_str()]),
+            JsonValue::Array(values) => values.get(token.parse::<usize>().ok()?),
+            _ => None,
+        }
+    })
+}
+
+fn violation(key_path: &[PathSegment], message: String) -> ConstInitError {
+    ConstInitError::new(ErrorKind::SchemaViolation(message)).in_key_path(key_path)
+}
+
+fn invalid_schema(message: &str) -> ConstInitError {
+    ConstInitError::new(ErrorKind::InvalidSchema(message.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(contents: &str) -> Schema {
+        Schema::from_json(json::parse(contents).unwrap()).unwrap()
+    }
+
+    fn violations(schema: &Schema, contents: &str) -> Vec<(Option<String>, String)> {
+        schema
+            .validate(&json::parse(contents).unwrap())
+            .iter()
+            .map(|error| (error.key_path(), error.kind().to_string()))
+            .collect()
+    }
+
+    const SCHEMA: &str = r##"{
+        "type": "object",
+        "required": ["server", "name"],
+        "additionalProperties": false,
+        "properties": {
+            "name": { "type": "string", "minLength": 1, "pattern": "^[a-z]+$" },
+            "mode": { "enum": ["fast", "safe"] },
+            "ratio": { "type": "number", "exclusiveMinimum": 0, "maximum": 1 },
+            "proxy": { "type": ["string", "null"] },
+            "server": {
+                "type": "object",
+                "required": ["port"],
+                "properties": {
+                    "port": { "$ref": "#/$defs/port" },
+                    "hosts": { "type": "array", "maxItems": 2, "items": { "type": "string" } }
+                }
+            }
+        },
+        "$defs": {
+            "port": { "type": "integer", "minimum": 0, "maximum": 65535 }
+        }
+    }"##;
+
+    #[test]
+    fn test_validate() {
+        let schema = schema(SCHEMA);
+        let valid = r#"{
+            "name": "app",
+            "mode": "fast",
+            "ratio": 1,
+            "proxy": null,
+            "server": { "port": 8080, "hosts": ["a", "b"] }
+        }"#;
+        assert_eq!(violations(&schema, valid), []);
+
+        let invalid = r#"{
+            "name": "App",
+            "mode": "slow",
+            "ratio": 0,
+            "proxy": 1,
+            "server": { "port": 80.5, "hosts": ["a", 1, "c"] },
+            "extra": true
+        }"#;
+        let path = |path: &str| Some(path.to_string());
+        let message =
+            |message: &str| format!("the configuration doesn't match its schema: {message}");
+        assert_eq!(
+            violations(&schema, invalid),
+            [
+                (
+                    path("name"),
+                    message(r#""App" doesn't match the pattern `^[a-z]+$`"#)
+                ),
+                (
+                    path("mode"),
+                    message(r#""slow" is not one of the allowed values: "fast", "safe""#)
+                ),
+                (path("ratio"), message("0 must be greater than 0")),
+                (
+                    path("proxy"),
+                    message("expected string or null, found integer")
+                ),
+                (
+                    path("server.port"),
+                    message("expected integer, found number")
+                ),
+                (
+                    path("server.hosts"),
+                    message("the array has 3 elements, more than 2")
+                ),
+                (
+                    path("server.hosts[1]"),
+                    message("expected string, found integer")
+                ),
+                (
+                    path("extra"),
+                    message("this key is not allowed by the schema")
+                ),
+            ]
+        );
+
+        assert_eq!(
+            violations(&schema, r#"{ "server": { "port": 70000 } }"#),
+            [
+                (None, message("the required key `name` is missing")),
+                (
+                    path("server.port"),
+                    message("70000 is greater than the maximum 65535")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_schema_types() {
+        let schema = schema(SCHEMA);
+        let types = schema.types(IntegerType::I64, FloatType::F32);
+        let keys = |keys: &[&str]| keys.iter().map(|key| key.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            [
+                (keys(&["ratio"]), RustType::F32),
+                (keys(&["proxy"]), RustType::Str("&str".to_string())),
+                (
+                    keys(&["server", "port"]),
+                    RustType::Integer(IntegerType::U16)
+                ),
+            ]
+        );
+
+        let key = |key: &str| PathSegment::Key(key.to_string());
+        let enums = schema.enums();
+        assert_eq!(enums.len(), 1);
+        assert_eq!(enums[0].0, [key("mode")]);
+        assert_eq!(enums[0].1, ["fast", "safe"]);
+    }
+
+    #[test]
+    fn invalid_schemas_are_an_error() {
+        for (invalid, key_path) in [
+            (
+                r#"{ "properties": { "a": { "type": "text" } } }"#,
+                "properties.a.type",
+            ),
+            (r#"{ "type": ["string", 1] }"#, "type"),
+            (r#"{ "items": [{ "pattern": "(" }] }"#, "items[0].pattern"),
+            (r##"{ "$ref": "#/$defs/port" }"##, "$ref"),
+            (r#"{ "$ref": "other.json" }"#, "$ref"),
+        ] {
+            let error = Schema::from_json(json::parse(invalid).unwrap()).unwrap_err();
+            assert!(
+                matches!(error.kind(), ErrorKind::InvalidSchema(_)),
+                "{invalid}"
+            );
+            assert_eq!(error.key_path().as_deref(), Some(key_path), "{invalid}");
+        }
+        // Keywords holding values are not schemas
+        schema(r#"{ "enum": [{ "type": "text" }], "default": { "pattern": "(" } }"#);
+    }
+}
@@ -0,0 +1,172 @@
+use crate::error::{ConstInitError, ErrorKind};
+use crate::merge::{ArrayMerge, merge};
+use json::JsonValue;
+
+// Resolve the conditional sections of `json`: the keys like `$profile.release`,
+// `$feature.telemetry` or `$target.arch.aarch64` whose object is merged into the object
+// containing them when their condition holds, and which are removed otherwise. The conditions
+// are checked against the environment variables given by `env` in the build script.
+// The `$schema` keys linking the file to its JSON Schema for editors are removed as well,
+// the other keys starting with `$` are left to the other passes.
+pub(crate) fn select_sections(
+    json: &mut JsonValue,
+    arrays: ArrayMerge,
+    env: &impl Fn(&str) -> Option<String>,
+) -> Result<(), ConstInitError> {
+    match json {
+        JsonValue::Object(object) => {
+            object.remove("$schema");
+            let sections: Vec<String> = object
+                .iter()
+                .map(|(key, _)| key)
+                .filter(|key| is_section(key))
+                .map(str::to_string)
+                .collect();
+            let mut selected = Vec::new();
+            for key in sections {
+                let section = object.remove(&key).unwrap_or(JsonValue::Null);
+                let condition = &key[1..];
+                if !section.is_object() {
+                    return Err(invalid_section("a section must be an object").in_key(&key));
+                }
+                if is_met(condition, env).map_err(|error| error.in_key(&key))? {
+                    selected.push((key, section));
+                }
+            }
+            let keys: Vec<String> = object.iter().map(|(key, _)| key.to_string()).collect();
+            for key in keys {
+                if let Some(value) = object.get_mut(&key) {
+                    select_sections(value, arrays, env).map_err(|error| error.in_key(&key))?;
+                }
+            }
+            // The sections are merged in their order of appearance, after being resolved
+            for (key, mut section) in selected {
+                select_sections(&mut section, arrays, env).map_err(|error| error.in_key(&key))?;
+                merge(json, section, arrays);
+            }
+        }
+        JsonValue::Array(values) => {
+            for (index, value) in values.iter_mut().enumerate() {
+                select_sections(value, arrays, env).map_err(|error| error.in_index(index))?;
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+fn is_section(key: &str) -> bool {
+    ["$profile.", "$feature.", "$target."]
+        .iter()
+        .any(|prefix| key.starts_with(prefix))
+}
+
+// Whether the condition of a section holds in the build script
+fn is_met(condition: &str, env: &impl Fn(&str) -> Option<String>) -> Result<bool, ConstInitError> {
+    match condition.split_once('.') {
+        // `debug` or `release`, custom profiles take the value of the profile they inherit from
+        Some(("profile", profile)) => Ok(env("PROFILE").is_some_and(|current| current == profile)),
+        Some(("feature", feature)) => {
+            let variable = format!("CARGO_FEATURE_{}", feature.to_uppercase().replace('-', "_"));
+            Ok(env(&variable).is_some())
+        }
+        // `$target.os.linux` is checked against `CARGO_CFG_TARGET_OS`, which describes the
+        // target of the compilation and not the machine running the build script.
+        // Some of these variables are lists, e.g. `CARGO_CFG_TARGET_FAMILY=unix,wasm`.
+        Some(("target", target)) => match target.split_once('.') {
+            Some((cfg, value)) => {
+                let variable = format!("CARGO_CFG_TARGET_{}", cfg.to_uppercase());
+                Ok(env(&variable).is_some_and(|values| values.split(',').any(|v| v == value)))
+            }
+            None => Err(invalid_section(
+                "a target section must be `$target.<cfg>.<value>`, e.g. `$target.os.linux`",
+            )),
+        },
+        _ => Err(invalid_section(
+            "unknown section, expected `$profile.<name>`, `$feature.<name>` or `$target.<cfg>.<value>`",
+        )),
+    }
+}
+
+fn invalid_section(message: &str) -> ConstInitError {
+    ConstInitError::new(ErrorKind::Unsupported(message.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn select(contents: &str, variables: &[(&str, &str)]) -> Result<String, ConstInitError> {
+        let mut json = json::parse(contents).unwrap();
+        let env = |name: &str| {
+            variables
+                .iter()
+                .find(|(variable, _)| *variable == name)
+                .map(|(_, value)| value.to_string())
+        };
+        select_sections(&mut json, ArrayMerge::Replace, &env).map(|()| json.dump())
+    }
+
+    #[test]
+    fn test_sections() {
+        let contents = r#"{
+            "log": { "level": "debug", "color": true },
+            "telemetry": false,
+            "$profile.release": { "log": { "level": "warn" } },
+            "$feature.telemetry": { "telemetry": true },
+            "server": { "workers": 1, "$profile.release": { "workers": 8 } }
+        }"#;
+        let debug =
+            r#"{"log":{"level":"debug","color":true},"telemetry":false,"server":{"workers":1}}"#;
+        assert_eq!(select(contents, &[("PROFILE", "debug")]).unwrap(), debug);
+
+        let release =
+            r#"{"log":{"level":"warn","color":true},"telemetry":true,"server":{"workers":8}}"#;
+        let variables = [("PROFILE", "release"), ("CARGO_FEATURE_TELEMETRY", "1")];
+        assert_eq!(select(contents, &variables).unwrap(), release);
+    }
+
+    #[test]
+    fn test_target_sections() {
+        let contents = r#"{
+            "buffer_size": 4096,
+            "threads": 4,
+            "$target.arch.aarch64": { "buffer_size": 16384 },
+            "$target.pointer_width.32": { "threads": 2 },
+            "$target.family.wasm": { "threads": 1 }
+        }"#;
+        let variables = [
+            ("CARGO_CFG_TARGET_ARCH", "aarch64"),
+            ("CARGO_CFG_TARGET_POINTER_WIDTH", "64"),
+            ("CARGO_CFG_TARGET_FAMILY", "unix"),
+        ];
+        let aarch64 = r#"{"buffer_size":16384,"threads":4}"#;
+        assert_eq!(select(contents, &variables).unwrap(), aarch64);
+
+        let variables = [
+            ("CARGO_CFG_TARGET_ARCH", "wasm32"),
+            ("CARGO_CFG_TARGET_POINTER_WIDTH", "32"),
+            ("CARGO_CFG_TARGET_FAMILY", "unix,wasm"),
+        ];
+        let wasm32 = r#"{"buffer_size":4096,"threads":1}"#;
+        assert_eq!(select(contents, &variables).unwrap(), wasm32);
+
+        assert!(select(r#"{ "$target.linux": {} }"#, &[]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_sections() {
+        let error = select(r#"{ "a": { "$target.linux": {} } }"#, &[]).unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::Unsupported(_)));
+        assert_eq!(error.key_path().as_deref(), Some("a.$target.linux"));
+
+        let error = select(r#"{ "$profile.release": 1 }"#, &[]).unwrap_err();
+        assert_eq!(error.key_path().as_deref(), Some("$profile.release"));
+
+        // Annotations and the other keys starting with `$` are not sections
+        let annotated = r#"{"$doc":"Settings","$doc.a":"A","a":1,"$id":"settings"}"#;
+        assert_eq!(select(annotated, &[]).unwrap(), annotated);
+        let linked = r#"{ "$schema": "./settings.schema.json", "port": 1 }"#;
+        assert_eq!(select(linked, &[]).unwrap(), r#"{"port":1}"#);
+    }
+}
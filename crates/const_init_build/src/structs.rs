@@ -0,0 +1,297 @@
+use crate::enums::{EnumType, Enums};
+use crate::error::{ConstInitError, ErrorKind, PathSegment};
+use crate::float::FloatType;
+use crate::integer::IntegerType;
+use crate::naming::{Namespace, Naming, Scope, identifier};
+use crate::types::{RustType, TypeMap, has_null};
+use json::JsonValue;
+
+// Arrays of objects are turned into arrays of structs, as well as objects when
+// `Generator::structs` is enabled. The struct of an array is inferred from all its
+// elements: its fields are the union of their keys, and the fields missing or null
+// in some elements are `Option`s. Objects inside the elements become nested structs,
+// named after the struct containing them and their key.
+pub(crate) struct Structs<'a> {
+    pub(crate) type_map: &'a TypeMap,
+    pub(crate) enums: &'a Enums,
+    pub(crate) integer_type: IntegerType,
+    pub(crate) float_type: FloatType,
+    pub(crate) naming: Naming,
+    pub(crate) visibility: &'a str,
+    // Path of the constant containing the structs, to find their types in the type map
+    pub(crate) key_path: &'a [PathSegment],
+    // Names of the structs, which must be different from each other
+    pub(crate) names: Scope,
+    // Names and definitions of the structs, the nested ones before the ones containing them
+    pub(crate) definitions: Vec<(String, String)>,
+}
+
+// The values of a same key in several objects, each with its path from the constant.
+// The value is `None` when the key is missing in the object.
+type Column<'a> = Vec<(Vec<PathSegment>, Option<&'a JsonValue>)>;
+
+impl Structs<'_> {
+    // The Rust type and the Rust literal of the object or array of objects named `name`
+    pub(crate) fn constant(
+        &mut self,
+        name: &str,
+        json: &JsonValue,
+    ) -> Result<(String, String), ConstInitError> {
+        let (rust_type, mut literals) = self.column("", name, &vec![(Vec::new(), Some(json))])?;
+        Ok((rust_type, literals.remove(0)))
+    }
+
+    // The Rust type shared by the values of `column` and their Rust literals,
+    // `prefix` is the name of the struct containing them
+    fn column(
+        &mut self,
+        prefix: &str,
+        key: &str,
+        column: &Column,
+    ) -> Result<(String, Vec<String>), ConstInitError> {
+        let (path, _) = &column[0];
+        let type_from_map = self.type_map.get(&[self.key_path, path].concat());
+        let present: Column = column
+            .iter()
+            .filter(|(_, value)| value.is_some_and(|value| !value.is_null()))
+            .cloned()
+            .collect();
+
+        // Missing and null values make the type optional, unless the type map already does
+        if present.len() < column.len() && !matches!(type_from_map, Some(RustType::Option(_))) {
+            if present.is_empty() {
+                return Err(ConstInitError::new(ErrorKind::NullValue).in_key_path(path));
+            }
+            let (rust_type, literals) = self.column(prefix, key, &present)?;
+            let mut literals = literals.into_iter();
+            let literals = column
+                .iter()
+                .map(|(_, value)| match value {
+                    Some(value) if !value.is_null() => {
+                        format!("Some({})", literals.next().unwrap_or_default())
+                    }
+                    _ => "None".to_string(),
+                })
+                .collect();
+            return Ok((format!("Option<{rust_type}>"), literals));
+        }
+
+        if let Some(allowed) = self.enums.get(&[self.key_path, path].concat()) {
+            return self.enum_column(prefix, key, allowed, column);
+        }
+
+        let values: Vec<&JsonValue> = column.iter().filter_map(|(_, value)| *value).collect();
+        if type_from_map.is_none() && values.len() == column.len() {
+            if values.iter().all(|value| value.is_object()) {
+                let name = identifier(&format!("{prefix}{}", self.naming.type_name(key)));
+                return self.define_struct(&name, column);
+            }
+            // Empty arrays are kept so that the difference of length is reported
+            if values.iter().any(|value| is_array_of_objects(value))
+                && values
+                    .iter()
+                    .all(|value| value.is_array() && value.members().all(JsonValue::is_object))
+            {
+                let name = format!("{prefix}{}", self.naming.type_name(&singular(key)));
+                let name = identifier(&name);
+                return self.array_of_structs(&name, column);
+            }
+        }
+
+        let rust_type = match type_from_map {
+            Some(rust_type) => rust_type.clone(),
+            None => {
+                match RustType::infer(values.iter().copied(), self.integer_type, self.float_type) {
+                    Ok(Some(rust_type)) if values.iter().any(|value| has_null(value)) => {
+                        RustType::Option(Box::new(rust_type))
+                    }
+                    Ok(Some(rust_type)) => rust_type,
+                    Ok(None) => {
+                        return Err(ConstInitError::new(ErrorKind::NullValue).in_key_path(path));
+                    }
+                    Err(error) => return Err(error.in_key_path(path)),
+                }
+            }
+        };
+        let mut column_type = None;
+        let mut literals = Vec::new();
+        for (path, value) in column {
+            let (value_type, literal) = match value {
+                Some(value) => rust_type
+                    .type_and_value(value)
+                    .map_err(|error| error.in_key_path(path))?,
+                // Only reached when the type map gives an `Option` type
+                None => (rust_type.name(), "None".to_string()),
+            };
+            // Arrays of different lengths don't have the same type
+            if column_type.get_or_insert_with(|| value_type.clone()) != &value_type {
+                return Err(ConstInitError::new(ErrorKind::MixedArray).in_key_path(path));
+            }
+            literals.push(literal);
+        }
+        // The fields of a struct need an explicit lifetime
+        let column_type = column_type
+            .unwrap_or_default()
+            .replace("&str", "&'static str");
+        Ok((column_type, literals))
+    }
+
+    // Define the struct `name` from the objects of `column` and return their literals
+    fn define_struct(
+        &mut self,
+        name: &str,
+        column: &Column,
+    ) -> Result<(String, Vec<String>), ConstInitError> {
+        let objects: Vec<(&Vec<PathSegment>, &json::object::Object)> = column
+            .iter()
+            .filter_map(|(path, value)| match value {
+                Some(JsonValue::Object(object)) => Some((path, object)),
+                _ => None,
+            })
+            .collect();
+        let mut keys: Vec<&str> = Vec::new();
+        for (_, object) in objects.iter() {
+            for (key, _) in object.iter() {
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+        }
+
+        let (path, _) = &column[0];
+        self.names
+            .declare(Namespace::Type, name, &[self.key_path, path].concat())
+            .map_err(|error| error.in_key_path(path))?;
+
+        let visibility = self.visibility;
+        let mut field_names = Scope::default();
+        let mut definition = "#[derive(Debug, Clone, Copy, PartialEq)]\n".to_string();
+        definition.push_str(&format!("{visibility} struct {name} {{\n"));
+        let mut fields = vec![Vec::new(); objects.len()];
+        for key in keys {
+            let field_column: Column = objects
+                .iter()
+                .map(|(path, object)| {
+                    let path = [path.as_slice(), &[PathSegment::Key(key.to_owned())]].concat();
+                    (path, object.get(key))
+                })
+                .collect();
+            let (field_path, _) = &field_column[0];
+            let field_name = identifier(&self.naming.module(key));
+            field_names
+                .declare(
+                    Namespace::Value,
+                    &field_name,
+                    &[self.key_path, field_path].concat(),
+                )
+                .map_err(|error| error.in_key_path(field_path))?;
+            let (field_type, literals) = self.column(name, key, &field_column)?;
+            definition.push_str(&format!("\t{visibility} {field_name}: {field_type},\n"));
+            for (fields, literal) in fields.iter_mut().zip(literals) {
+                fields.push(format!("{field_name}: {literal}"));
+            }
+        }
+        definition.push_str("}\n");
+        self.definitions.push((name.to_string(), definition));
+
+        let literals = fields
+            .into_iter()
+            .map(|fields| match fields.is_empty() {
+                true => format!("{name} {{}}"),
+                false => format!("{name} {{ {} }}", fields.join(", ")),
+            })
+            .collect();
+        Ok((name.to_string(), literals))
+    }
+
+    // Strings with a fixed set of allowed values, their enum is named like a struct
+    fn enum_column(
+        &mut self,
+        prefix: &str,
+        key: &str,
+        allowed: &[String],
+        column: &Column,
+    ) -> Result<(String, Vec<String>), ConstInitError> {
+        let (path, _) = &column[0];
+        let name = identifier(&format!("{prefix}{}", self.naming.type_name(key)));
+        let enum_type =
+            EnumType::new(name, allowed, self.naming).map_err(|error| error.in_key_path(path))?;
+        self.names
+            .declare(
+                Namespace::Type,
+                &enum_type.name,
+                &[self.key_path, path].concat(),
+            )
+            .map_err(|error| error.in_key_path(path))?;
+        let literals = column
+            .iter()
+            .map(|(path, value)| {
+                enum_type
+                    .literal(value.unwrap_or(&JsonValue::Null))
+                    .map_err(|error| error.in_key_path(path))
+            })
+            .collect::<Result<_, _>>()?;
+        let definition = enum_type.definition(self.visibility);
+        self.definitions.push((enum_type.name.clone(), definition));
+        Ok((enum_type.name, literals))
+    }
+
+    // Arrays of objects in several objects, which must all have the same length
+    fn array_of_structs(
+        &mut self,
+        name: &str,
+        column: &Column,
+    ) -> Result<(String, Vec<String>), ConstInitError> {
+        let mut elements = Vec::new();
+        for (path, value) in column {
+            for (index, element) in value.iter().flat_map(|value| value.members()).enumerate() {
+                let path = [path.as_slice(), &[PathSegment::Index(index)]].concat();
+                elements.push((path, Some(element)));
+            }
+        }
+        let (struct_type, literals) = self.define_struct(name, &elements)?;
+
+        let mut literals = literals.into_iter();
+        let mut len = None;
+        let mut arrays = Vec::new();
+        for (path, value) in column {
+            let value_len = value.map_or(0, JsonValue::len);
+            if *len.get_or_insert(value_len) != value_len {
+                return Err(ConstInitError::new(ErrorKind::MixedArray).in_key_path(path));
+            }
+            let elements: Vec<String> = literals.by_ref().take(value_len).collect();
+            arrays.push(format!("[{}]", elements.join(",")));
+        }
+        Ok((format!("[{struct_type}; {}]", len.unwrap_or(0)), arrays))
+    }
+}
+
+pub(crate) fn is_array_of_objects(json: &JsonValue) -> bool {
+    match json {
+        JsonValue::Array(values) => !values.is_empty() && values.iter().all(JsonValue::is_object),
+        _ => false,
+    }
+}
+
+// The name of an element of the array `name`, e.g. `backend` for `backends`
+fn singular(name: &str) -> String {
+    if let Some(stem) = name.strip_suffix("ies") {
+        format!("{stem}y")
+    } else if name.ends_with('s') && !name.ends_with("ss") {
+        name[..name.len() - 1].to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_struct_names() {
+        assert_eq!(singular("backends"), "backend");
+        assert_eq!(singular("proxies"), "proxy");
+        assert_eq!(singular("address"), "address");
+    }
+}
@@ -0,0 +1,323 @@
+use crate::error::{ConstInitError, ErrorKind, LineStarts, Location, PathSegment, panic_on_error};
+use crate::float::check_float_text;
+use crate::generator::{Format, Generator, KeyLocations};
+use crate::json::float_to_number;
+use json::{JsonValue, object::Object};
+use std::path::Path;
+use toml::de::{DeTable, DeValue};
+use toml::{Table, Value};
+
+/// This function is used in build scripts to generate a rust file from a TOML file.
+/// The output file contains all the variables from input file as rust constants,
+/// with the same layout as [`generate_constants_from_json`](crate::generate_constants_from_json).
+///
+/// TOML specific values are handled as follows:
+/// - TOML `integers` are turned into Rust `isize` and TOML `floats` into Rust `f64`,
+///   even when the float has an integral value such as `3.0`
+/// - TOML `datetimes` are turned into Rust `&str` in their RFC 3339 representation
+/// - TOML `arrays of tables` are turned into arrays of structs, like JSON arrays of objects
+///
+/// # Panics
+///
+/// Panics if does not find the input TOML file, can't parse it, can't turn one of its values
+/// into a Rust constant or can't write the output rust file.
+/// See [`try_generate_constants_from_toml`] for a version returning an error instead.
+///
+/// # Example
+///
+/// ```rust,no_run,file:build.rs
+/// use std::path::PathBuf;
+/// use const_init_build::generate_constants_from_toml;
+/// fn main() {
+///     let manifest_path = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+///     // We read the settings from "settings.toml" file
+///     let toml_input: PathBuf = [&manifest_path, "settings.toml"].iter().collect();
+///     // We output "settings.rs" containing the variables of "settings.toml" as constants
+///     let rust_output: PathBuf = [&manifest_path, "examples", "generated", "settings.rs"]
+///         .iter()
+///         .collect();
+///
+///     generate_constants_from_toml(&toml_input, &rust_output);
+/// }
+/// ```
+pub fn generate_constants_from_toml<P: AsRef<Path>>(input_toml_file: P, output_rust_file: P) {
+    panic_on_error(try_generate_constants_from_toml(
+        input_toml_file,
+        output_rust_file,
+    ));
+}
+
+/// Same as [`generate_constants_from_toml`] but returns a [`ConstInitError`]
+/// describing which key of the input file is at fault instead of panicking.
+pub fn try_generate_constants_from_toml<P: AsRef<Path>>(
+    input_toml_file: P,
+    output_rust_file: P,
+) -> Result<(), ConstInitError> {
+    Generator::new()
+        .input(input_toml_file)
+        .output(output_rust_file)
+        .format(Format::Toml)
+        .run()
+}
+
+pub(crate) fn parse_toml(contents: &str) -> Result<JsonValue, ConstInitError> {
+    let table: Table = contents.parse().map_err(|error: toml::de::Error| {
+        let parse_error = ConstInitError::new(ErrorKind::Parse(error.message().to_string()));
+        match error.span() {
+            Some(span) => parse_error.at(Location::from_offset(contents, span.start)),
+            None => parse_error,
+        }
+    })?;
+    let document = DeTable::parse(contents)
+        .map_err(|error| ConstInitError::new(ErrorKind::Parse(error.message().to_string())))?;
+    check_floats(contents, &DeValue::Table(document.into_inner()))?;
+    table_to_json(&table)
+}
+
+// The floats are parsed as `f64`, those which aren't read exactly are an error
+// like in JSON files, which is checked with their text
+fn check_floats(contents: &str, value: &DeValue) -> Result<(), ConstInitError> {
+    let values: Vec<_> = match value {
+        DeValue::Table(table) => table.values().collect(),
+        DeValue::Array(values) => values.iter().collect(),
+        _ => Vec::new(),
+    };
+    for value in values {
+        if let DeValue::Float(float) = value.get_ref() {
+            check_float_text(float.as_str())
+                .map_err(|error| error.at(Location::from_offset(contents, value.span().start)))?;
+        }
+        check_floats(contents, value.get_ref())?;
+    }
+    Ok(())
+}
+
+// Find the position of the key at `key_path` in a TOML text, with the spans of the keys.
+// The key of a table is the one of its first header, e.g. `[server]`.
+pub(crate) fn locate_toml_key(contents: &str, key_path: &[PathSegment]) -> Option<Location> {
+    let table = DeTable::parse(contents).ok()?;
+    let mut value: &DeValue = &DeValue::Table(table.into_inner());
+    let mut offset = 0;
+    for segment in key_path {
+        let element = match (segment, value) {
+            (PathSegment::Key(key), DeValue::Table(table)) => {
+                let (key, value) = table.iter().find(|(current, _)| current.get_ref() == key)?;
+                offset = key.span().start;
+                value
+            }
+            (PathSegment::Index(index), DeValue::Array(values)) => {
+                let value = values.get(*index)?;
+                offset = value.span().start;
+                value
+            }
+            _ => return None,
+        };
+        value = element.get_ref();
+    }
+    Some(Location::from_offset(contents, offset))
+}
+
+// The locations of the keys of the tables of a TOML text which are not in arrays,
+// with the same spans as `locate_toml_key`
+pub(crate) fn toml_key_locations(contents: &str) -> KeyLocations {
+    fn collect(
+        table: &DeTable,
+        key_path: &mut Vec<PathSegment>,
+        lines: &LineStarts,
+        locations: &mut KeyLocations,
+    ) {
+        for (key, value) in table.iter() {
+            key_path.push(PathSegment::Key(key.get_ref().to_string()));
+            locations.insert(key_path.clone(), lines.location(key.span().start));
+            if let DeValue::Table(table) = value.get_ref() {
+                collect(table, key_path, lines, locations);
+            }
+            key_path.pop();
+        }
+    }
+    let mut locations = KeyLocations::new();
+    if let Ok(table) = DeTable::parse(contents) {
+        let lines = LineStarts::new(contents);
+        collect(table.get_ref(), &mut Vec::new(), &lines, &mut locations);
+    }
+    locations
+}
+
+// Turn a TOML table into a JSON object, keeping the order of the keys
+fn table_to_json(table: &Table) -> Result<JsonValue, ConstInitError> {
+    let mut object = Object::with_capacity(table.len());
+    for (key, value) in table.iter() {
+        object.insert(key, toml_to_json(value).map_err(|error| error.in_key(key))?);
+    }
+    Ok(JsonValue::Object(object))
+}
+
+fn toml_to_json(value: &Value) -> Result<JsonValue, ConstInitError> {
+    let json = match value {
+        Value::String(string) => JsonValue::String(string.to_owned()),
+        Value::Integer(integer) => JsonValue::Number((*integer).into()),
+        Value::Float(float) => {
+            if float.is_nan() {
+                return Err(ConstInitError::new(ErrorKind::NanValue));
+            }
+            if float.is_infinite() {
+                return Err(ConstInitError::new(ErrorKind::InfiniteValue));
+            }
+            JsonValue::Number(float_to_number(*float))
+        }
+        Value::Boolean(boolean) => JsonValue::Boolean(*boolean),
+        Value::Datetime(datetime) => JsonValue::String(datetime.to_string()),
+        Value::Array(values) => JsonValue::Array(
+            values
+                .iter()
+                .enumerate()
+                .map(|(index, value)| toml_to_json(value).map_err(|error| error.in_index(index)))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Table(table) => table_to_json(table)?,
+    };
+    Ok(json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn toml_to_constants(input: &str) -> Result<String, ConstInitError> {
+        let table: Table = input.parse().unwrap();
+        let mut generated = String::new();
+        Generator::new().json_to_constants(&mut generated, &table_to_json(&table)?, &[])?;
+        Ok(generated.split_whitespace().collect())
+    }
+
+    #[test]
+    fn test_toml_basic_types() {
+        let generated = toml_to_constants(
+            r#"
+a = true
+b = 234
+c = "azer"
+e = 3.14
+f = -35
+g = [1, 2, 3, 4]
+h = ["abc", "def", "hij"]
+i = 3.0
+j = 1979-05-27T07:32:00Z
+k = 07:32:00
+"#,
+        )
+        .unwrap();
+
+        let expected: String = r#"
+pub const A: bool = true;
+pub const B: isize = 234;
+pub const C: &str = "azer";
+pub const E: f64 = 3.14;
+pub const F: isize = -35;
+pub const G: [isize; 4] = [1,2,3,4];
+pub const H: [&str; 3] = ["abc","def","hij"];
+pub const I: f64 = 3.0;
+pub const J: &str = "1979-05-27T07:32:00Z";
+pub const K: &str = "07:32:00";
+"#
+        .split_whitespace()
+        .collect();
+        assert_eq!(generated, expected)
+    }
+
+    #[test]
+    fn test_toml_tables_and_arrays_of_tables() {
+        let generated = toml_to_constants(
+            r#"
+z = 1
+
+[a]
+d = false
+
+[a.h]
+i = [1.5, 2.0]
+
+[[servers]]
+name = "alpha"
+port = 8080
+
+[[servers]]
+name = "beta"
+port = 8081
+"#,
+        )
+        .unwrap();
+
+        let expected: String = r#"
+pub const Z: isize = 1;
+pub mod a {
+        pub const D: bool = false;
+        pub mod h {
+                pub const I: [f64; 2] = [1.5,2.0];
+        }
+}
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Server {
+        pub name: &'static str,
+        pub port: isize,
+}
+pub const SERVERS: [Server; 2] = [
+        Server { name: "alpha", port: 8080 },
+        Server { name: "beta", port: 8081 }
+];
+"#
+        .split_whitespace()
+        .collect();
+        assert_eq!(generated, expected)
+    }
+
+    #[test]
+    fn toml_nan_is_an_error() {
+        let error = toml_to_constants("[a]\nb = [1.0, nan]").unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::NanValue));
+        assert_eq!(error.key_path().as_deref(), Some("a.b[1]"));
+    }
+
+    #[test]
+    fn inexact_toml_floats_are_an_error() {
+        assert!(
+            parse_toml(
+                "a = 0.1
+b = [1.5e3, 1_000.25]"
+            )
+            .is_ok()
+        );
+        let error = parse_toml(
+            "a = 1
+[b]
+long = 0.123456789012345678901",
+        )
+        .unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::Unsupported(_)));
+        assert_eq!(error.location().map(|location| location.line), Some(3));
+        let error = parse_toml("a = [0.5, 0.12345678901234567]").unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::InexactNumber { .. }));
+        assert_eq!(error.location().map(|location| location.column), Some(11));
+        let error = parse_toml("a = 1_000.123_456_789_012_34").unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::InexactNumber { .. }));
+        assert!(parse_toml("a = 1_000.5e1_0").is_ok());
+    }
+
+    #[test]
+    fn test_locate_toml_key() {
+        let contents = "a = 1\n[server]\nport = 2\nlimits.max = 3\n[[backends]]\nname = 'a'\n[[backends]]\nname = 'b'\n";
+        let line = |key_path: &[PathSegment]| {
+            locate_toml_key(contents, key_path).map(|location| location.line)
+        };
+        let key = |key: &str| PathSegment::Key(key.to_string());
+        assert_eq!(line(&[key("a")]), Some(1));
+        assert_eq!(line(&[key("server")]), Some(2));
+        assert_eq!(line(&[key("server"), key("port")]), Some(3));
+        assert_eq!(line(&[key("server"), key("limits"), key("max")]), Some(4));
+        assert_eq!(
+            line(&[key("backends"), PathSegment::Index(1), key("name")]),
+            Some(8)
+        );
+        assert_eq!(line(&[key("server"), key("missing")]), None);
+    }
+}
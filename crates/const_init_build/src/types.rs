@@ -0,0 +1,399 @@
+use crate::error::{ConstInitError, ErrorKind, PathSegment};
+use crate::float::FloatType;
+use crate::generator::string_literal;
+use crate::integer::{Integer, IntegerType};
+use json::JsonValue;
+
+// Rust types given to the values of the configuration file by a type map
+// (e.g. `settings.types.toml`) instead of the types inferred from the values.
+// The keys of the type map follow the structure of the configuration file
+// and their values are the names of the Rust types, e.g. `server.port = "u16"`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TypeMap {
+    types: Vec<(Vec<String>, RustType)>,
+}
+
+// A Rust type which can be given to a value, for arrays it is the type of their elements
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RustType {
+    Integer(IntegerType),
+    F32,
+    F64,
+    Bool,
+    // `&str` or `&'static str`, kept as written in the type map
+    Str(String),
+    Char,
+    // Given in the type map or used for null values
+    Option(Box<RustType>),
+}
+
+impl TypeMap {
+    pub(crate) fn from_json(json: &JsonValue) -> Result<TypeMap, ConstInitError> {
+        let mut type_map = TypeMap::default();
+        match json {
+            JsonValue::Object(_) => type_map.insert(json, &mut Vec::new())?,
+            _ => {
+                return Err(invalid_type_map(
+                    "the root of the type map must be an object",
+                ));
+            }
+        }
+        Ok(type_map)
+    }
+
+    fn insert(&mut self, json: &JsonValue, keys: &mut Vec<String>) -> Result<(), ConstInitError> {
+        match json {
+            JsonValue::Object(object) => {
+                for (key, value) in object.iter() {
+                    keys.push(key.to_owned());
+                    self.insert(value, keys)
+                        .map_err(|error| error.in_key(key))?;
+                    keys.pop();
+                }
+            }
+            _ => {
+                let name = json
+                    .as_str()
+                    .ok_or_else(|| invalid_type_map("types must be given as strings"))?;
+                let rust_type = RustType::parse(name)
+                    .ok_or_else(|| invalid_type_map(&format!("unknown type `{name}`")))?;
+                self.types.push((keys.clone(), rust_type));
+            }
+        }
+        Ok(())
+    }
+
+    // The type given to the value at `key_path`. Indexes are skipped so that
+    // a type applies to a key in every element of an array of objects.
+    pub(crate) fn get(&self, key_path: &[PathSegment]) -> Option<&RustType> {
+        let keys = key_path.iter().filter_map(|segment| match segment {
+            PathSegment::Key(key) => Some(key),
+            PathSegment::Index(_) => None,
+        });
+        self.types
+            .iter()
+            .find(|(type_keys, _)| type_keys.iter().eq(keys.clone()))
+            .map(|(_, rust_type)| rust_type)
+    }
+
+    // Add types for other keys, the types already in the type map take precedence
+    pub(crate) fn extend(&mut self, types: Vec<(Vec<String>, RustType)>) {
+        self.types.extend(types);
+    }
+
+    // Every key of the type map must lead to a value of the configuration file,
+    // otherwise it is most likely a typo
+    pub(crate) fn check_keys(&self, json: &JsonValue) -> Result<(), ConstInitError> {
+        for (keys, _) in self.types.iter() {
+            if !has_value(json, keys) {
+                let error = invalid_type_map("no value of the configuration file has this key");
+                return Err(keys
+                    .iter()
+                    .rev()
+                    .fold(error, |error, key| error.in_key(key)));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn has_value(json: &JsonValue, keys: &[String]) -> bool {
+    match keys.split_first() {
+        None => match json {
+            JsonValue::Object(_) => false,
+            JsonValue::Array(values) => !values.iter().any(JsonValue::is_object),
+            _ => true,
+        },
+        Some((key, rest)) => match json {
+            JsonValue::Object(object) => {
+                object.get(key).is_some_and(|value| has_value(value, rest))
+            }
+            JsonValue::Array(values) => values.iter().any(|value| has_value(value, keys)),
+            _ => false,
+        },
+    }
+}
+
+impl RustType {
+    fn parse(name: &str) -> Option<RustType> {
+        let rust_type = match name.trim() {
+            "f32" => RustType::F32,
+            "f64" => RustType::F64,
+            "bool" => RustType::Bool,
+            "char" => RustType::Char,
+            name @ ("&str" | "&'static str") => RustType::Str(name.to_string()),
+            name => match name
+                .strip_prefix("Option<")
+                .and_then(|name| name.strip_suffix('>'))
+            {
+                Some(name) => RustType::Option(Box::new(RustType::parse(name)?)),
+                None => RustType::Integer(IntegerType::from_name(name)?),
+            },
+        };
+        Some(rust_type)
+    }
+
+    // The type of the non-null values among `values` and their elements,
+    // `None` when there are only null values
+    pub(crate) fn infer<'a>(
+        values: impl IntoIterator<Item = &'a JsonValue>,
+        integer_type: IntegerType,
+        float_type: FloatType,
+    ) -> Result<Option<RustType>, ConstInitError> {
+        let mut leaves = Vec::new();
+        for value in values {
+            collect_leaves(value, &mut leaves);
+        }
+        let rust_type = if leaves.is_empty() {
+            return Ok(None);
+        } else if leaves.iter().all(|leaf| leaf.is_string()) {
+            RustType::Str("&str".to_string())
+        } else if leaves.iter().all(|leaf| leaf.is_boolean()) {
+            RustType::Bool
+        } else if leaves.iter().all(|leaf| leaf.is_number()) {
+            let integers = leaves
+                .iter()
+                .filter_map(|leaf| match leaf {
+                    JsonValue::Number(number) => Integer::from_number(number),
+                    _ => None,
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            // Integers mixed with floats are turned into floats
+            match integers.len() {
+                len if len == leaves.len() => RustType::Integer(integer_type.resolve(&integers)?),
+                _ => match float_type {
+                    FloatType::F32 => RustType::F32,
+                    FloatType::F64 => RustType::F64,
+                },
+            }
+        } else if leaves.iter().any(|leaf| leaf.is_object()) {
+            return Err(ConstInitError::new(ErrorKind::Unsupported(
+                "objects inside arrays of other values are not handled".to_string(),
+            )));
+        } else {
+            return Err(ConstInitError::new(ErrorKind::MixedArray));
+        };
+        Ok(Some(rust_type))
+    }
+
+    pub(crate) fn name(&self) -> String {
+        match self {
+            RustType::Integer(integer_type) => integer_type.as_str().to_string(),
+            RustType::F32 => "f32".to_string(),
+            RustType::F64 => "f64".to_string(),
+            RustType::Bool => "bool".to_string(),
+            RustType::Str(name) => name.to_owned(),
+            RustType::Char => "char".to_string(),
+            RustType::Option(rust_type) => format!("Option<{}>", rust_type.name()),
+        }
+    }
+
+    // The Rust type and the Rust literal of `json`, which must fit in this type
+    pub(crate) fn type_and_value(
+        &self,
+        json: &JsonValue,
+    ) -> Result<(String, String), ConstInitError> {
+        // Null values turn the type into an `Option`, for arrays it is the type of their elements
+        if !matches!(self, RustType::Option(_)) && has_null(json) {
+            return RustType::Option(Box::new(self.clone())).type_and_value(json);
+        }
+        if let JsonValue::Array(values) = json {
+            let mut literals = Vec::new();
+            let mut element_type = None;
+            for (index, value) in values.iter().enumerate() {
+                let (value_type, literal) = self
+                    .type_and_value(value)
+                    .map_err(|error| error.in_index(index))?;
+                // Nested arrays must all have the same length
+                if element_type.get_or_insert_with(|| value_type.clone()) != &value_type {
+                    return Err(ConstInitError::new(ErrorKind::MixedArray));
+                }
+                literals.push(literal);
+            }
+            let element_type = element_type.unwrap_or_else(|| self.name());
+            return Ok((
+                format!("[{element_type}; {}]", values.len()),
+                format!("[{}]", literals.join(",")),
+            ));
+        }
+
+        let literal = match (self, json) {
+            (RustType::Option(_), JsonValue::Null) => "None".to_string(),
+            (RustType::Option(rust_type), _) => {
+                let (_, literal) = rust_type.type_and_value(json)?;
+                format!("Some({literal})")
+            }
+            (_, JsonValue::Null) => return Err(ConstInitError::new(ErrorKind::NullValue)),
+            (_, JsonValue::Object(_)) => {
+                return Err(ConstInitError::new(ErrorKind::Unsupported(
+                    "objects inside arrays of other values are not handled".to_string(),
+                )));
+            }
+            (_, JsonValue::Number(number)) if number.is_nan() => {
+                return Err(ConstInitError::new(ErrorKind::NanValue));
+            }
+            (RustType::Integer(integer_type), JsonValue::Number(number)) => {
+                match Integer::from_number(number) {
+                    Some(integer) => {
+                        let integer = integer?;
+                        integer_type.resolve(&[integer])?;
+                        integer.to_string()
+                    }
+                    None => return Err(self.mismatch(json)),
+                }
+            }
+            (RustType::F32, JsonValue::Number(number)) => FloatType::F32.literal(number)?,
+            (RustType::F64, JsonValue::Number(number)) => FloatType::F64.literal(number)?,
+            (RustType::Bool, JsonValue::Boolean(_)) => json.to_string(),
+            (RustType::Str(_), JsonValue::Short(_) | JsonValue::String(_)) => {
+                string_literal(json.as_str().unwrap_or_default())
+            }
+            (RustType::Char, JsonValue::Short(_) | JsonValue::String(_)) => {
+                let mut chars = json.as_str().unwrap_or_default().chars();
+                match (chars.next(), chars.next()) {
+                    (Some(char), None) => format!("{char:?}"),
+                    _ => return Err(self.mismatch(json)),
+                }
+            }
+            _ => return Err(self.mismatch(json)),
+        };
+        Ok((self.name(), literal))
+    }
+
+    fn mismatch(&self, json: &JsonValue) -> ConstInitError {
+        ConstInitError::new(ErrorKind::TypeMismatch {
+            value: json.dump(),
+            rust_type: self.name(),
+        })
+    }
+}
+
+// Whether `json` or one of its elements is null
+pub(crate) fn has_null(json: &JsonValue) -> bool {
+    match json {
+        JsonValue::Null => true,
+        JsonValue::Array(values) => values.iter().any(has_null),
+        _ => false,
+    }
+}
+
+// Whether `json` is an array mixing integers and floats
+pub(crate) fn has_mixed_numbers(json: &JsonValue) -> bool {
+    let mut leaves = Vec::new();
+    collect_leaves(json, &mut leaves);
+    let is_float = |leaf: &&JsonValue| matches!(leaf, JsonValue::Number(number) if Integer::from_number(number).is_none());
+    leaves.iter().all(|leaf| leaf.is_number())
+        && leaves.iter().any(is_float)
+        && !leaves.iter().all(is_float)
+}
+
+fn collect_leaves<'a>(json: &'a JsonValue, leaves: &mut Vec<&'a JsonValue>) {
+    match json {
+        JsonValue::Null => (),
+        JsonValue::Array(values) => {
+            for value in values {
+                collect_leaves(value, leaves);
+            }
+        }
+        _ => leaves.push(json),
+    }
+}
+
+fn invalid_type_map(message: &str) -> ConstInitError {
+    ConstInitError::new(ErrorKind::InvalidTypeMap(message.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(rust_type: &str, value: &str) -> Result<(String, String), ConstInitError> {
+        RustType::parse(rust_type)
+            .unwrap()
+            .type_and_value(&json::parse(value).unwrap())
+    }
+
+    #[test]
+    fn test_type_and_value() {
+        let converted = |rust_type: &str, value: &str| convert(rust_type, value).unwrap();
+        assert_eq!(converted("u16", "8080"), ("u16".into(), "8080".into()));
+        assert_eq!(converted("f32", "0.5"), ("f32".into(), "0.5".into()));
+        assert_eq!(converted("f64", "2"), ("f64".into(), "2.0".into()));
+        assert_eq!(converted("char", r#""x""#), ("char".into(), "'x'".into()));
+        assert_eq!(
+            converted("&'static str", r#""abc""#),
+            ("&'static str".into(), r#""abc""#.into())
+        );
+        assert_eq!(
+            converted("i8", "[[1, -2], [3, 4]]"),
+            ("[[i8; 2]; 2]".into(), "[[1,-2],[3,4]]".into())
+        );
+        assert_eq!(converted("u8", "[]"), ("[u8; 0]".into(), "[]".into()));
+        assert_eq!(
+            converted("Option<u16>", "8080"),
+            ("Option<u16>".into(), "Some(8080)".into())
+        );
+        assert_eq!(
+            converted("&str", "null"),
+            ("Option<&str>".into(), "None".into())
+        );
+        assert_eq!(
+            converted("f64", "[1.5, null]"),
+            ("[Option<f64>; 2]".into(), "[Some(1.5),None]".into())
+        );
+    }
+
+    #[test]
+    fn test_value_must_fit_the_type() {
+        let error = convert("u16", "65536").unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::IntegerOutOfRange { .. }));
+
+        let error = convert("u16", "[1, 1.5]").unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::TypeMismatch { value, rust_type } if value == "1.5" && rust_type == "u16"
+        ));
+        assert_eq!(error.key_path().as_deref(), Some("[1]"));
+
+        let error = convert("u8", "[[1, 2], [3]]").unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::MixedArray));
+        assert!(convert("char", r#""ab""#).is_err());
+        assert!(convert("f32", "1e39").is_err());
+        assert!(convert("bool", "1").is_err());
+        assert!(convert("&str", "true").is_err());
+    }
+
+    #[test]
+    fn test_type_map() {
+        let config = json::parse(
+            r#"{ "server": { "port": 8080, "hosts": [{ "name": "a" }, { "id": 1 }] } }"#,
+        )
+        .unwrap();
+        let type_map = TypeMap::from_json(
+            &json::parse(r#"{ "server": { "port": "u16", "hosts": { "id": "u8" } } }"#).unwrap(),
+        )
+        .unwrap();
+        type_map.check_keys(&config).unwrap();
+        let key_path = [
+            PathSegment::Key("server".into()),
+            PathSegment::Key("hosts".into()),
+            PathSegment::Index(1),
+            PathSegment::Key("id".into()),
+        ];
+        assert_eq!(
+            type_map.get(&key_path),
+            Some(&RustType::Integer(IntegerType::U8))
+        );
+        assert_eq!(type_map.get(&key_path[..2]), None);
+
+        let type_map = TypeMap::from_json(&json::parse(r#"{ "server": "u16" }"#).unwrap()).unwrap();
+        let error = type_map.check_keys(&config).unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::InvalidTypeMap(_)));
+        assert_eq!(error.key_path().as_deref(), Some("server"));
+
+        let error =
+            TypeMap::from_json(&json::parse(r#"{ "a": { "b": "u17" } }"#).unwrap()).unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::InvalidTypeMap(_)));
+        assert_eq!(error.key_path().as_deref(), Some("a.b"));
+    }
+}
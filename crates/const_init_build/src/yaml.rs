@@ -0,0 +1,599 @@
+use crate::error::{ConstInitError, ErrorKind, Location, PathSegment, panic_on_error};
+use crate::float::check_float_text;
+use crate::generator::{Format, Generator, KeyLocations};
+use crate::json::float_to_number;
+use json::{JsonValue, object::Object};
+use serde_yaml::{Mapping, Value};
+use std::path::Path;
+
+/// This function is used in build scripts to generate a rust file from a YAML file.
+/// The output file contains all the variables from input file as rust constants,
+/// with the same layout as [`generate_constants_from_json`](crate::generate_constants_from_json).
+///
+/// Anchors and aliases (`&base`, `*base`) are resolved and merge keys (`<<: *base`)
+/// are applied before generating the constants, keys set explicitly in a mapping
+/// take precedence over the merged ones.
+///
+/// # Panics
+///
+/// Panics if does not find the input YAML file, can't parse it, can't turn one of its values
+/// into a Rust constant or can't write the output rust file.
+/// See [`try_generate_constants_from_yaml`] for a version returning an error instead.
+///
+/// # Example
+///
+/// ```rust,no_run,file:build.rs
+/// use std::path::PathBuf;
+/// use const_init_build::generate_constants_from_yaml;
+/// fn main() {
+///     let manifest_path = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+///     // We read the settings from "settings.yaml" file
+///     let yaml_input: PathBuf = [&manifest_path, "settings.yaml"].iter().collect();
+///     // We output "settings.rs" containing the variables of "settings.yaml" as constants
+///     let rust_output: PathBuf = [&manifest_path, "examples", "generated", "settings.rs"]
+///         .iter()
+///         .collect();
+///
+///     generate_constants_from_yaml(&yaml_input, &rust_output);
+/// }
+/// ```
+pub fn generate_constants_from_yaml<P: AsRef<Path>>(input_yaml_file: P, output_rust_file: P) {
+    panic_on_error(try_generate_constants_from_yaml(
+        input_yaml_file,
+        output_rust_file,
+    ));
+}
+
+/// Same as [`generate_constants_from_yaml`] but returns a [`ConstInitError`]
+/// describing which key of the input file is at fault instead of panicking.
+pub fn try_generate_constants_from_yaml<P: AsRef<Path>>(
+    input_yaml_file: P,
+    output_rust_file: P,
+) -> Result<(), ConstInitError> {
+    Generator::new()
+        .input(input_yaml_file)
+        .output(output_rust_file)
+        .format(Format::Yaml)
+        .run()
+}
+
+pub(crate) fn parse_yaml(contents: &str) -> Result<JsonValue, ConstInitError> {
+    let yaml: Value = serde_yaml::from_str(contents).map_err(|error| {
+        let parse_error = ConstInitError::new(ErrorKind::Parse(error.to_string()));
+        match error.location() {
+            Some(location) => parse_error.at(Location {
+                line: location.line(),
+                column: location.column(),
+            }),
+            None => parse_error,
+        }
+    })?;
+    check_floats(contents)?;
+    yaml_to_json(&yaml)
+}
+
+// The floats are parsed as `f64`, those which aren't read exactly are an error like in JSON
+// files. The YAML parser doesn't keep their text, so it is taken from the plain scalars of
+// the file which look like numbers.
+fn check_floats(contents: &str) -> Result<(), ConstInitError> {
+    for (offset, scalar) in plain_scalars(contents) {
+        if is_yaml_number(scalar) {
+            check_float_text(scalar)
+                .map_err(|error| error.at(Location::from_offset(contents, offset)))?;
+        }
+    }
+    Ok(())
+}
+
+// The unquoted values of a YAML text with their offset, leaving out the keys, the comments,
+// the quoted strings and the content of block scalars (`|` and `>`). This is only used to
+// check numbers so it stays lenient instead of handling every corner of YAML.
+fn plain_scalars(contents: &str) -> Vec<(usize, &str)> {
+    let mut scalars = Vec::new();
+    // The quote of the string being read, or `\` after a backslash in a double-quoted string
+    let mut quote = None;
+    let mut flow_depth = 0;
+    // The indentation of the line starting a block scalar, whose content is more indented
+    let mut block_scalar = None;
+    let mut line_offset = 0;
+    for line in contents.split_inclusive('\n') {
+        let offset = line_offset;
+        line_offset += line.len();
+        let line = line.trim_end_matches(['\n', '\r']);
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        if let Some(block_indent) = block_scalar {
+            if line.trim().is_empty() || indent > block_indent {
+                continue;
+            }
+            block_scalar = None;
+        }
+        let bytes = line.as_bytes();
+        let is_blank = |position: usize| bytes.get(position).is_none_or(u8::is_ascii_whitespace);
+        let mut start = None;
+        let mut tokens = Vec::new();
+        for (position, &byte) in bytes.iter().enumerate() {
+            match (quote, byte) {
+                (Some(b'"'), b'\\') => quote = Some(b'\\'),
+                (Some(b'\\'), _) => quote = Some(b'"'),
+                (Some(current), _) if current == byte => quote = None,
+                (Some(_), _) => (),
+                (None, b'#') if position == 0 || is_blank(position - 1) => break,
+                (None, b'"' | b'\'') if start.is_none() => quote = Some(byte),
+                (None, b'[' | b'{') if start.is_none() => flow_depth += 1,
+                (None, b']' | b'}' | b',') if flow_depth > 0 => {
+                    tokens.extend(start.take().map(|start| start..position));
+                    if byte != b',' {
+                        flow_depth -= 1;
+                    }
+                }
+                // The scalar before `:` is a key
+                (None, b':')
+                    if is_blank(position + 1)
+                        || (flow_depth > 0
+                            && matches!(bytes.get(position + 1), Some(b',' | b']' | b'}'))) =>
+                {
+                    start = None
+                }
+                (None, b'-') if start.is_none() && is_blank(position + 1) => (),
+                (None, b' ' | b'\t') if start.is_none() => (),
+                (None, _) => {
+                    start.get_or_insert(position);
+                }
+            }
+        }
+        if quote.is_none() {
+            tokens.extend(start.map(|start| start..line.len()));
+        }
+        for token in tokens {
+            let mut scalar = line[token].trim_end();
+            // Anchors and tags come before the value, e.g. `&default 0.5`
+            while scalar.starts_with(['&', '!']) {
+                scalar = scalar
+                    .split_once([' ', '\t'])
+                    .map_or("", |(_, value)| value.trim_start());
+            }
+            let is_block_scalar = scalar.starts_with(['|', '>'])
+                && scalar[1..]
+                    .chars()
+                    .all(|char| matches!(char, '-' | '+' | '0'..='9'));
+            if is_block_scalar && flow_depth == 0 {
+                block_scalar = Some(indent);
+            } else if !scalar.is_empty() {
+                let start = scalar.as_ptr() as usize - line.as_ptr() as usize;
+                scalars.push((offset + start, scalar));
+            }
+        }
+    }
+    scalars
+}
+
+// Whether a plain scalar is a decimal number for YAML, e.g. `-1`, `.5` or `1.5e-3`
+fn is_yaml_number(scalar: &str) -> bool {
+    let unsigned = scalar.strip_prefix(['-', '+']).unwrap_or(scalar);
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (unsigned, None),
+    };
+    let is_digits = |digits: &str| digits.bytes().all(|byte| byte.is_ascii_digit());
+    let (integral, fractional) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let exponent = exponent.map(|exponent| exponent.strip_prefix(['-', '+']).unwrap_or(exponent));
+    is_digits(integral)
+        && is_digits(fractional)
+        && !(integral.is_empty() && fractional.is_empty())
+        && exponent.is_none_or(|exponent| !exponent.is_empty() && is_digits(exponent))
+}
+
+fn yaml_to_json(value: &Value) -> Result<JsonValue, ConstInitError> {
+    let json = match value {
+        Value::Null => JsonValue::Null,
+        Value::Bool(boolean) => JsonValue::Boolean(*boolean),
+        Value::Number(number) => {
+            if let Some(integer) = number.as_i64() {
+                JsonValue::Number(integer.into())
+            } else if let Some(integer) = number.as_u64() {
+                JsonValue::Number(integer.into())
+            } else if number.is_nan() {
+                return Err(ConstInitError::new(ErrorKind::NanValue));
+            } else if number.is_infinite() {
+                return Err(ConstInitError::new(ErrorKind::InfiniteValue));
+            } else {
+                JsonValue::Number(float_to_number(number.as_f64().unwrap()))
+            }
+        }
+        Value::String(string) => JsonValue::String(string.to_owned()),
+        Value::Sequence(values) => JsonValue::Array(
+            values
+                .iter()
+                .enumerate()
+                .map(|(index, value)| yaml_to_json(value).map_err(|error| error.in_index(index)))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Mapping(mapping) => {
+            let mut object = Object::with_capacity(mapping.len());
+            insert_mapping(&mut object, mapping)?;
+            JsonValue::Object(object)
+        }
+        Value::Tagged(tagged) => {
+            return Err(unsupported(format!(
+                "YAML tags are not supported: {}",
+                tagged.tag
+            )));
+        }
+    };
+    Ok(json)
+}
+
+// Insert the entries of a YAML mapping into a JSON object, applying the merge keys
+// (https://yaml.org/type/merge.html) at the position where they appear.
+// Keys explicitly set in the mapping always win over the merged ones.
+fn insert_mapping(object: &mut Object, mapping: &Mapping) -> Result<(), ConstInitError> {
+    for (key, value) in mapping.iter() {
+        if key.as_str() == Some("<<") {
+            let merge_key_error = || unsupported("YAML merge key only accepts mappings".into());
+            // With a list of mappings to merge, the first ones take precedence
+            let merged: Vec<&Mapping> = match value {
+                Value::Mapping(merged) => vec![merged],
+                Value::Sequence(values) => values
+                    .iter()
+                    .map(|value| value.as_mapping().ok_or_else(merge_key_error))
+                    .collect::<Result<_, _>>()?,
+                _ => return Err(merge_key_error()),
+            };
+            for merged in merged {
+                let mut merged_object = Object::with_capacity(merged.len());
+                insert_mapping(&mut merged_object, merged)?;
+                for (merged_key, merged_value) in merged_object.iter() {
+                    let is_overridden = object.get(merged_key).is_some()
+                        || mapping
+                            .keys()
+                            .any(|key| key_to_string(key).is_ok_and(|key| key == merged_key));
+                    if !is_overridden {
+                        object.insert(merged_key, merged_value.clone());
+                    }
+                }
+            }
+        } else {
+            let key = key_to_string(key)?;
+            let value = yaml_to_json(value).map_err(|error| error.in_key(&key))?;
+            object.insert(&key, value);
+        }
+    }
+    Ok(())
+}
+
+// Find the position of the key at `key_path` in a YAML text. serde_yaml doesn't keep the
+// positions of the keys, they are found from the indentation of the block mappings and
+// sequences instead. Keys in flow collections (`{ a: 1 }`) or merged with `<<` aren't found.
+pub(crate) fn locate_yaml_key(contents: &str, key_path: &[PathSegment]) -> Option<Location> {
+    // Lines with their content, without the indentation
+    let lines: Vec<(usize, &str, &str)> = contents
+        .lines()
+        .enumerate()
+        .map(|(index, line)| {
+            let content = line.trim_start();
+            (index, line, content)
+        })
+        .filter(|(_, _, content)| !content.is_empty() && !content.starts_with('#'))
+        .collect();
+    let column = |line: &str, content: &str| line.len() - content.len();
+    // Position in `lines` of the line to search from, and column of the parent node
+    let (mut start, mut parent_column) = (0, None::<usize>);
+    // Whether the line at `start` is the first line of a sequence item
+    let mut item_line = false;
+    let mut location = None;
+    for segment in key_path {
+        let is_inside = |column: usize| parent_column.is_none_or(|parent| column > parent);
+        let mut found = None;
+        match segment {
+            PathSegment::Key(key) => {
+                // The keys of the mapping are the lines at the column of its first key
+                let mut key_column = None;
+                for (position, &(index, line, content)) in lines.iter().enumerate().skip(start) {
+                    // The first key of a sequence item is on the line of its `- `
+                    let content = match position == start && item_line {
+                        true => content.trim_start_matches('-').trim_start(),
+                        false => content,
+                    };
+                    let content_column = column(line, content);
+                    if !is_inside(content_column) {
+                        break;
+                    }
+                    if *key_column.get_or_insert(content_column) == content_column
+                        && is_yaml_key(content, key)
+                    {
+                        found = Some((position, index, content_column));
+                        break;
+                    }
+                }
+            }
+            PathSegment::Index(target) => {
+                let (mut items, mut sequence_column) = (0, None);
+                for (position, &(index, line, content)) in lines.iter().enumerate().skip(start) {
+                    let is_item = content == "-" || content.starts_with("- ");
+                    let item_column = column(line, content);
+                    // The items of a sequence can be at the column of the key containing it
+                    let is_outside = match parent_column {
+                        Some(parent) if is_item => item_column < parent,
+                        Some(parent) => item_column <= parent,
+                        None => false,
+                    };
+                    if is_outside {
+                        break;
+                    }
+                    if is_item && *sequence_column.get_or_insert(item_column) == item_column {
+                        if items == *target {
+                            found = Some((position, index, item_column));
+                            break;
+                        }
+                        items += 1;
+                    }
+                }
+            }
+        }
+        let (position, index, node_column) = found?;
+        item_line = matches!(segment, PathSegment::Index(_));
+        start = if item_line { position } else { position + 1 };
+        parent_column = Some(node_column);
+        location = Some(Location {
+            line: index + 1,
+            column: node_column + 1,
+        });
+    }
+    location
+}
+
+// The locations of the keys of the block mappings of a YAML text which are not in
+// sequences, found from the indentation of the lines like `locate_yaml_key` does
+pub(crate) fn yaml_key_locations(contents: &str) -> KeyLocations {
+    let mut locations = KeyLocations::new();
+    // The column and the key of the lines containing the current one,
+    // without key for the sequence items and the keys inside them
+    let mut parents: Vec<(usize, Option<&str>)> = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let content = line.trim_start();
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+        let column = line.len() - content.len();
+        while parents.last().is_some_and(|&(parent, _)| parent >= column) {
+            parents.pop();
+        }
+        let in_sequence = parents.iter().any(|(_, key)| key.is_none());
+        let key = match content == "-" || content.starts_with("- ") {
+            true => None,
+            false => match yaml_key(content) {
+                Some(key) => Some(key).filter(|_| !in_sequence),
+                None => continue,
+            },
+        };
+        if let Some(key) = key {
+            let key_path = parents
+                .iter()
+                .filter_map(|(_, key)| *key)
+                .chain([key])
+                .map(|key| PathSegment::Key(key.to_string()))
+                .collect();
+            let location = Location {
+                line: index + 1,
+                column: column + 1,
+            };
+            locations.entry(key_path).or_insert(location);
+        }
+        parents.push((column, key));
+    }
+    locations
+}
+
+// The key of a line of a block mapping starting with `key:`, quoted or not
+fn yaml_key(content: &str) -> Option<&str> {
+    let (key, rest) = match content.strip_prefix(['"', '\'']) {
+        Some(quoted) => {
+            let quote = &content[..1];
+            let end = quoted.find(quote)?;
+            (&quoted[..end], &quoted[end + 1..])
+        }
+        None => {
+            let end = content
+                .match_indices(':')
+                .map(|(index, _)| index)
+                .find(|&index| {
+                    content[index + 1..].is_empty() || content[index + 1..].starts_with([' ', '\t'])
+                })?;
+            (&content[..end], &content[end..])
+        }
+    };
+    let rest = rest.strip_prefix(':')?;
+    (rest.is_empty() || rest.starts_with([' ', '\t'])).then_some(key)
+}
+
+// Whether a line of a block mapping starts with `key:`, quoted or not
+fn is_yaml_key(content: &str, key: &str) -> bool {
+    let rest = [
+        format!("{key}:"),
+        format!("\"{key}\":"),
+        format!("'{key}':"),
+    ]
+    .iter()
+    .find_map(|prefix| content.strip_prefix(prefix.as_str()));
+    rest.is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t']))
+}
+
+fn key_to_string(key: &Value) -> Result<String, ConstInitError> {
+    match key {
+        Value::String(string) => Ok(string.to_owned()),
+        Value::Bool(boolean) => Ok(boolean.to_string()),
+        Value::Number(number) => Ok(number.to_string()),
+        _ => Err(unsupported(
+            "YAML keys must be strings, numbers or booleans".into(),
+        )),
+    }
+}
+
+fn unsupported(message: String) -> ConstInitError {
+    ConstInitError::new(ErrorKind::Unsupported(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inexact_yaml_floats_are_an_error() {
+        let contents = r#"
+ratio: 0.1
+version: "0.123456789012345678901"
+# long: 0.123456789012345678901
+text: |
+  0.123456789012345678901
+values: [1.5e3, .5, -2]
+base: &base 2.5
+0.123456789012345678901: key
+"#;
+        assert_eq!(
+            plain_scalars(contents)
+                .into_iter()
+                .map(|(_, scalar)| scalar)
+                .collect::<Vec<_>>(),
+            ["0.1", "1.5e3", ".5", "-2", "2.5", "key"]
+        );
+        assert!(parse_yaml(contents).is_ok());
+
+        let error = parse_yaml("a: 1\nb:\n  long: 0.123456789012345678901\n").unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::Unsupported(_)));
+        let location = error.location().unwrap();
+        assert_eq!((location.line, location.column), (3, 9));
+        let error = parse_yaml("a:\n  - 0.5\n  - 0.12345678901234567\n").unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::InexactNumber { .. }));
+        assert!(!is_yaml_number("1.2.3") && !is_yaml_number("1e") && !is_yaml_number("."));
+    }
+
+    fn yaml_to_constants(input: &str) -> Result<String, ConstInitError> {
+        let yaml: Value = serde_yaml::from_str(input).unwrap();
+        let mut generated = String::new();
+        Generator::new().json_to_constants(&mut generated, &yaml_to_json(&yaml)?, &[])?;
+        Ok(generated.split_whitespace().collect())
+    }
+
+    #[test]
+    fn test_yaml_basic_types() {
+        let generated = yaml_to_constants(
+            r#"
+a: true
+b: 234
+c: azer
+e: 3.14
+f: -35
+g: [1, 2, 3, 4]
+h:
+  - abc
+  - def
+i: 3.0
+"#,
+        )
+        .unwrap();
+
+        let expected: String = r#"
+pub const A: bool = true;
+pub const B: isize = 234;
+pub const C: &str = "azer";
+pub const E: f64 = 3.14;
+pub const F: isize = -35;
+pub const G: [isize; 4] = [1,2,3,4];
+pub const H: [&str; 2] = ["abc","def"];
+pub const I: f64 = 3.0;
+"#
+        .split_whitespace()
+        .collect();
+        assert_eq!(generated, expected)
+    }
+
+    #[test]
+    fn test_yaml_anchors_and_merge_keys() {
+        let generated = yaml_to_constants(
+            r#"
+base: &base
+  host: localhost
+  port: 80
+  tags: &tags [a, b]
+staging:
+  <<: *base
+  port: 8080
+production:
+  name: prod
+  <<: [{ port: 443 }, *base]
+  tags: *tags
+"#,
+        )
+        .unwrap();
+
+        let expected: String = r#"
+pub mod base {
+        pub const HOST: &str = "localhost";
+        pub const PORT: isize = 80;
+        pub const TAGS: [&str; 2] = ["a","b"];
+}
+pub mod staging {
+        pub const HOST: &str = "localhost";
+        pub const TAGS: [&str; 2] = ["a","b"];
+        pub const PORT: isize = 8080;
+}
+pub mod production {
+        pub const NAME: &str = "prod";
+        pub const PORT: isize = 443;
+        pub const HOST: &str = "localhost";
+        pub const TAGS: [&str; 2] = ["a","b"];
+}
+"#
+        .split_whitespace()
+        .collect();
+        assert_eq!(generated, expected)
+    }
+
+    #[test]
+    fn yaml_null_is_an_error() {
+        let error = yaml_to_constants("a: 1\nb:\n  c: ~").unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::NullValue));
+        assert_eq!(error.key_path().as_deref(), Some("b.c"));
+    }
+
+    #[test]
+    fn test_locate_yaml_key() {
+        let contents = r#"
+a: 1
+server:
+  # the port
+  limits:
+    port: 3
+  port: 2
+backends:
+- name: a
+  port: 80
+- name: b
+  "port": 81
+"#;
+        let location = |key_path: &[PathSegment]| {
+            locate_yaml_key(contents, key_path).map(|location| (location.line, location.column))
+        };
+        let key = |key: &str| PathSegment::Key(key.to_string());
+        assert_eq!(location(&[key("a")]), Some((2, 1)));
+        assert_eq!(location(&[key("server"), key("port")]), Some((7, 3)));
+        assert_eq!(
+            location(&[key("server"), key("limits"), key("port")]),
+            Some((6, 5))
+        );
+        assert_eq!(
+            location(&[key("backends"), PathSegment::Index(1)]),
+            Some((11, 1))
+        );
+        assert_eq!(
+            location(&[key("backends"), PathSegment::Index(1), key("name")]),
+            Some((11, 3))
+        );
+        assert_eq!(
+            location(&[key("backends"), PathSegment::Index(1), key("port")]),
+            Some((12, 3))
+        );
+        assert_eq!(location(&[key("backends"), PathSegment::Index(2)]), None);
+        assert_eq!(location(&[key("port")]), None);
+    }
+}
@@ -1,8 +1,11 @@
 //! When deriving `ConstInit` a struct will have a `const_init()` function which
 //! allows an initialization at build time.
 //!
-//! The fields are initialized with a global variable matching the field name in uppercase
-//! by default.
+//! The fields are initialized with a global variable matching the field name in
+//! `SCREAMING_SNAKE_CASE` by default, e.g. `font_size` or `fontSize` look for `FONT_SIZE`,
+//! like the constants generated by `const_init_build`.
+//! With `#[const_init(naming = "uppercase")]` on the struct, the field name is only uppercased
+//! instead, to match the constants generated with `const_init_build::Naming::Uppercase`.
 //! You can also specify which constant value you want to initialize your field with,
 //! using the attribute `#[const_init(value = {constant value/variable})]`.
 //!
@@ -63,6 +66,7 @@
 use proc_macro::TokenStream;
 
 mod macros;
+mod naming;
 
 #[proc_macro_derive(ConstInit, attributes(const_init))]
 pub fn derive_const_init(item: TokenStream) -> TokenStream {
@@ -1,3 +1,4 @@
+use crate::naming::{Naming, constant_name};
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::{DeriveInput, Expr, Ident};
@@ -22,14 +23,24 @@ fn expand_const_init(ast: DeriveInput) -> proc_macro2::TokenStream {
         .expect("ConstInit can only be derived for structs")
         .fields;
 
+    let naming = match opts.naming.as_deref().map(Naming::from_attribute) {
+        None => Naming::default(),
+        Some(Some(naming)) => naming,
+        Some(None) => {
+            return darling::Error::custom("`naming` should be \"snake_case\" or \"uppercase\"")
+                .with_span(&ast.ident)
+                .write_errors();
+        }
+    };
+
     let fields_init = fields.iter().map(|field| {
         let field_id = field.ident.as_ref().unwrap();
         if let Some(const_value) = field.value.as_ref() {
             // If a "value" attribute is indicated, pick this value
             quote! { #field_id: #const_value, }
         } else {
-            // else try with the field name in uppercase
-            let field_upper = syn::parse_str::<Expr>(&field_id.to_string().to_uppercase())
+            // else try with the field name in screaming snake case
+            let field_upper = syn::parse_str::<Expr>(&constant_name(&field_id.to_string(), naming))
                 .expect("Failed to parse field name into syn::Expr");
             quote! { #field_id: #field_upper, }
         }
@@ -61,6 +72,7 @@ use darling::{FromDeriveInput, FromField};
 #[darling(attributes(const_init), supports(struct_named))]
 struct ConstInitOpts {
     import_path: Option<Expr>,
+    naming: Option<String>,
     data: darling::ast::Data<darling::util::Ignored, FieldOpts>,
 }
 
@@ -0,0 +1,65 @@
+// Name of the constant looked for by a field without `value` attribute.
+// It must follow the `Naming` of `const_init_build` so that the fields find
+// the constants generated from the keys with the same name.
+pub(crate) fn constant_name(field: &str, naming: Naming) -> String {
+    let field = field.strip_prefix("r#").unwrap_or(field);
+    match naming {
+        Naming::SnakeCase => words(field).join("_").to_uppercase(),
+        Naming::Uppercase => field.to_uppercase(),
+    }
+}
+
+// Same as `const_init_build::Naming`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum Naming {
+    #[default]
+    SnakeCase,
+    Uppercase,
+}
+
+impl Naming {
+    pub(crate) fn from_attribute(naming: &str) -> Option<Naming> {
+        match naming {
+            "snake_case" => Some(Naming::SnakeCase),
+            "uppercase" => Some(Naming::Uppercase),
+            _ => None,
+        }
+    }
+}
+
+// Split a name into words on the characters which are not alphanumeric and before
+// the uppercase letters starting a word, like `const_init_build` does for the keys.
+// Both are checked to give the same names by the `derive_naming` test of `const_init_tests`.
+fn words(name: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = name.char_indices().collect();
+    let mut words = Vec::new();
+    let mut start = None;
+    for (position, &(index, char)) in chars.iter().enumerate() {
+        if !char.is_alphanumeric() {
+            if let Some(start) = start.take() {
+                words.push(&name[start..index]);
+            }
+            continue;
+        }
+        if let Some(word_start) = start
+            && char.is_uppercase()
+        {
+            let previous = chars[position - 1].1;
+            let next_is_lowercase = chars
+                .get(position + 1)
+                .is_some_and(|(_, next)| next.is_lowercase());
+            if previous.is_lowercase()
+                || previous.is_numeric()
+                || (previous.is_uppercase() && next_is_lowercase)
+            {
+                words.push(&name[word_start..index]);
+                start = Some(index);
+            }
+        }
+        start.get_or_insert(index);
+    }
+    if let Some(start) = start {
+        words.push(&name[start..]);
+    }
+    words
+}
@@ -0,0 +1,50 @@
+use std::path::PathBuf;
+
+use const_init_build::{Generator, Naming};
+
+fn main() {
+    let manifest_path = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+
+    // Strings which are hard to write as Rust literals, checked by "tests/string_literals.rs"
+    let strings_input: PathBuf = [&manifest_path, "fixtures", "strings.json"]
+        .iter()
+        .collect();
+    Generator::new()
+        .input(&strings_input)
+        .output_to_out_dir("strings")
+        .run()
+        .unwrap_or_else(|error| panic!("{error}"));
+
+    // Strings with a fixed set of values, checked by "tests/enums.rs"
+    let enums_input: PathBuf = [&manifest_path, "fixtures", "enums.json"].iter().collect();
+    Generator::new()
+        .input(&enums_input)
+        .output_to_out_dir("enums")
+        .run()
+        .unwrap_or_else(|error| panic!("{error}"));
+
+    // Keys named like the fields of the structs deriving `ConstInit`, both naming conventions
+    // are checked by "tests/derive_naming.rs"
+    let naming_input: PathBuf = [&manifest_path, "fixtures", "naming.json"].iter().collect();
+    Generator::new()
+        .input(&naming_input)
+        .output_to_out_dir("naming")
+        .run()
+        .unwrap_or_else(|error| panic!("{error}"));
+    Generator::new()
+        .input(&naming_input)
+        .output_to_out_dir("naming_uppercase")
+        .naming(Naming::Uppercase)
+        .run()
+        .unwrap_or_else(|error| panic!("{error}"));
+
+    // The booleans and "a.d" set cfgs, checked by "tests/cfg_flags.rs"
+    let cfg_input: PathBuf = [&manifest_path, "fixtures", "cfg.json"].iter().collect();
+    Generator::new()
+        .input(&cfg_input)
+        .output_to_out_dir("cfg")
+        .cfg_prefix("const_init")
+        .cfg_value("a.d")
+        .run()
+        .unwrap_or_else(|error| panic!("{error}"));
+}
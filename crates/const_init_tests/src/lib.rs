@@ -0,0 +1,4 @@
+//! Not published: its build script generates constants from the files of `fixtures` with
+//! `const_init_build`, and its tests include them with `const_init::include_settings!`.
+//! These fixtures are kept out of the `const_init` package, whose build script runs
+//! in every crate depending on it.
@@ -0,0 +1,25 @@
+//! The cfgs set by "build.rs" from the booleans and the selected strings of "fixtures/cfg.json"
+//! must follow the generated constants. Unknown cfgs would be reported by `check-cfg`.
+
+mod cfg {
+    const_init::include_settings!("cfg");
+}
+
+#[cfg(not(const_init_foo))]
+compile_error!("`foo` is true in cfg.json");
+
+#[cfg(const_init_telemetry)]
+compile_error!("`telemetry` is false in cfg.json");
+
+#[cfg(const_init_a_d = "ding!")]
+fn d() -> &'static str {
+    "ding!"
+}
+
+#[test]
+fn cfgs_follow_the_settings() {
+    assert_eq!(cfg!(const_init_foo), cfg::FOO);
+    assert_eq!(cfg!(const_init_telemetry), cfg::TELEMETRY);
+    assert_eq!(cfg!(const_init_a_d = "dong!"), cfg::a::D == "dong!");
+    assert_eq!(d(), cfg::a::D);
+}
@@ -0,0 +1,57 @@
+//! The `ConstInit` derive macro splits the field names into words on its own, it must find
+//! the constants generated by "build.rs" from the keys of "fixtures/naming.json" with the
+//! same names as the fields.
+#![allow(non_snake_case)]
+
+use const_init::ConstInit;
+
+mod naming {
+    const_init::include_settings!("naming");
+}
+mod naming_uppercase {
+    const_init::include_settings!("naming_uppercase");
+}
+
+#[derive(ConstInit)]
+#[const_init(import_path = crate::naming)]
+struct SnakeCase {
+    fontSize: isize,
+    HTTPServer: &'static str,
+    v2Api: bool,
+    max_connections_2: isize,
+    userID: isize,
+    r#type: &'static str,
+}
+
+#[derive(ConstInit)]
+#[const_init(import_path = crate::naming_uppercase, naming = "uppercase")]
+struct Uppercase {
+    fontSize: isize,
+    HTTPServer: &'static str,
+    v2Api: bool,
+    max_connections_2: isize,
+    userID: isize,
+    r#type: &'static str,
+}
+
+#[test]
+fn fields_find_the_constants_of_their_keys() {
+    const SNAKE_CASE: SnakeCase = SnakeCase::const_init();
+    assert_eq!(naming::FONT_SIZE, SNAKE_CASE.fontSize);
+    assert_eq!(naming::HTTP_SERVER, SNAKE_CASE.HTTPServer);
+    assert_eq!(naming::V2_API, SNAKE_CASE.v2Api);
+    assert_eq!(naming::MAX_CONNECTIONS_2, SNAKE_CASE.max_connections_2);
+    assert_eq!(naming::USER_ID, SNAKE_CASE.userID);
+    assert_eq!(naming::TYPE, SNAKE_CASE.r#type);
+
+    const UPPERCASE: Uppercase = Uppercase::const_init();
+    assert_eq!(naming_uppercase::FONTSIZE, UPPERCASE.fontSize);
+    assert_eq!(naming_uppercase::HTTPSERVER, UPPERCASE.HTTPServer);
+    assert_eq!(naming_uppercase::V2API, UPPERCASE.v2Api);
+    assert_eq!(
+        naming_uppercase::MAX_CONNECTIONS_2,
+        UPPERCASE.max_connections_2
+    );
+    assert_eq!(naming_uppercase::USERID, UPPERCASE.userID);
+    assert_eq!(naming_uppercase::TYPE, UPPERCASE.r#type);
+}
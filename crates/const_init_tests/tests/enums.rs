@@ -0,0 +1,22 @@
+//! The enums generated from the `$enum` annotations of "fixtures/enums.json"
+//! by "build.rs" must compile and be usable in constant contexts.
+
+mod enums {
+    const_init::include_settings!("enums");
+}
+use enums::*;
+
+// Unlike comparisons of `&str`, enums can be matched in constant contexts
+const SAFE: bool = matches!(MODE, Mode::SafeMode);
+const LOG_LEVEL: &str = server::LOG_LEVEL.as_str();
+
+#[test]
+fn enum_constants_follow_the_settings() {
+    assert_eq!(MODE, Mode::SafeMode);
+    assert_eq!(MODE.as_str(), "safe-mode");
+    assert_eq!(SAFE, MODE == Mode::SafeMode);
+    assert_eq!(LOG_LEVEL, "warn");
+    assert_eq!(server::LOG_LEVEL, server::LogLevel::Warn);
+    let roles = BACKENDS.map(|backend| backend.role);
+    assert_eq!(roles, [BackendRole::Primary, BackendRole::Replica]);
+}
@@ -0,0 +1,34 @@
+//! The string constants generated from "fixtures/strings.json" by "build.rs"
+//! must be the same strings as the ones parsed from the JSON file.
+
+mod strings {
+    const_init::include_settings!("strings");
+}
+
+#[test]
+fn string_constants_round_trip() {
+    let json: serde_json::Value =
+        serde_json::from_str(include_str!("../fixtures/strings.json")).unwrap();
+    let constants = [
+        ("empty", strings::EMPTY),
+        ("quotes", strings::QUOTES),
+        ("backslashes", strings::BACKSLASHES),
+        ("escapes", strings::ESCAPES),
+        ("multi_line", strings::MULTI_LINE),
+        ("multi_line_hashes", strings::MULTI_LINE_HASHES),
+        ("multi_line_crlf", strings::MULTI_LINE_CRLF),
+        ("multi_line_control", strings::MULTI_LINE_CONTROL),
+        ("braces", strings::BRACES),
+        ("unicode", strings::UNICODE),
+        ("astral", strings::ASTRAL),
+        ("combining", strings::COMBINING),
+        ("zero_width", strings::ZERO_WIDTH),
+        ("bidi", strings::BIDI),
+        ("line_separators", strings::LINE_SEPARATORS),
+    ];
+    let keys = json.as_object().unwrap();
+    assert_eq!(keys.len(), constants.len(), "every string must be checked");
+    for (key, constant) in constants {
+        assert_eq!(Some(constant), json[key].as_str(), "{key}");
+    }
+}
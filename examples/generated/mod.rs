@@ -1,3 +1,6 @@
 //! Module for generated files
 
-pub mod settings;
+// Constants generated from "settings.json" by "build.rs"
+pub mod settings {
+    const_init::include_settings!("settings");
+}
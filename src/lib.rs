@@ -0,0 +1,47 @@
+//! Constant initialization of your types with values obtained from a configuration file.
+//!
+//! This crate gathers the runtime side of the workflow:
+//! - the [`ConstInit`] derive macro from
+//!   [`const_init_macros`](https://docs.rs/const_init_macros/latest/const_init_macros/index.html)
+//! - the [`include_settings!`] macro to include the constants generated in `OUT_DIR` by
+//!   [`const_init_build`](https://docs.rs/const_init_build/latest/const_init_build/index.html)
+
+pub use const_init_macros::ConstInit;
+
+/// Include the constants generated in `OUT_DIR` by a build script using
+/// `const_init_build::Generator::output_to_out_dir`.
+///
+/// The name given to the macro is the one given to `output_to_out_dir`,
+/// the constants are then available in the module where the macro is called.
+///
+/// # Example
+///
+/// _build.rs:_
+/// ```rust,ignore
+/// fn main() {
+///     const_init_build::Generator::new()
+///         .input("settings.json")
+///         .output_to_out_dir("settings")
+///         .run()
+///         .unwrap();
+/// }
+/// ```
+///
+/// _main.rs:_
+/// ```rust,ignore
+/// mod settings {
+///     const_init::include_settings!("settings");
+/// }
+/// use settings::*;
+/// ```
+#[macro_export]
+macro_rules! include_settings {
+    ($name:literal) => {
+        #[allow(dead_code, clippy::all)]
+        mod __const_init_settings {
+            include!(concat!(env!("OUT_DIR"), "/", $name, ".rs"));
+        }
+        #[allow(unused_imports)]
+        pub use __const_init_settings::*;
+    };
+}
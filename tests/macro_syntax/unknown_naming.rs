@@ -0,0 +1,11 @@
+use const_init_macros::ConstInit;
+
+const FOO: bool = true;
+
+#[derive(ConstInit)]
+#[const_init(naming = "camelCase")]
+struct FooBar {
+    foo: bool,
+}
+
+fn main() {}
@@ -7,12 +7,8 @@ const EXAMPLES: [&str; 3] = ["with_const_variables", "with_derive_macro", "with_
 fn branches_are_optimized_away_in_examples() {
     let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
 
-    // Path to the generated file
-    let generated_file_path = [&manifest_dir, "examples", "generated", "settings.rs"];
-    let generated_file_path: PathBuf = generated_file_path.iter().collect();
-
     // Generate the file containing the constants from "settings.json" as "settings.rs"
-    // with "build.rs" and build the examples
+    // in `OUT_DIR` with "build.rs" and build the examples
     let status = Command::new("cargo")
         .arg("build")
         .arg("--examples") // build all examples
@@ -21,7 +17,7 @@ fn branches_are_optimized_away_in_examples() {
         .expect("Failed to run cargo build");
 
     assert!(status.success(), "cargo build failed");
-    assert!(generated_file_path.exists());
+    assert!(generated_file_exists(&manifest_dir));
 
     for example in EXAMPLES {
         let mut binary_path = vec![&manifest_dir, "target", "release", "examples"];
@@ -58,3 +54,14 @@ fn branches_are_optimized_away_in_examples() {
         );
     }
 }
+
+// The generated file is in the `OUT_DIR` of the build script,
+// which looks like "target/release/build/const_init-{hash}/out"
+fn generated_file_exists(manifest_dir: &str) -> bool {
+    let build_dir: PathBuf = [manifest_dir, "target", "release", "build"].iter().collect();
+    std::fs::read_dir(build_dir)
+        .expect("Failed to read the build directory")
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("const_init-"))
+        .any(|entry| entry.path().join("out").join("settings.rs").exists())
+}